@@ -6,7 +6,7 @@ use std::{
 
 use anyhow::{Context as _, Result};
 use clap::Parser;
-use schema::Acceleration;
+use schema::{Acceleration, TapAxis, TapEvent, TapKind, TapSign, TIMESTAMP_RESOLUTION_US};
 use serde::{Deserialize, Serialize};
 use tokio::time::Instant;
 
@@ -26,6 +26,7 @@ async fn main() {
     let cli = Cli::parse();
 
     let fetcher = AccelFetcher::new(&cli.addr);
+    let tap_fetcher = TapFetcher::new(&cli.addr);
 
     let path = match cli.out {
         Some(out) if out.is_dir() => out.join(format!("{}.csv", ts())),
@@ -45,11 +46,15 @@ async fn main() {
         let now = Instant::now();
 
         if let Ok(data) = fetcher.fetch().await {
-            let row = CsvRow::now(&data);
-            if cli.verbose {
-                println!("{row:?}");
+            // 取れなくても加速度は書く
+            let taps = tap_fetcher.fetch().await.unwrap_or_default();
+            let rows = std::iter::once(CsvRow::now(&data)).chain(taps.iter().map(CsvRow::tap));
+            for row in rows {
+                if cli.verbose {
+                    println!("{row:?}");
+                }
+                writer.serialize(&row).expect("Failed to write csv row.");
             }
-            writer.serialize(&row).expect("Failed to write csv row.");
             writer.flush().expect("Failed to flush csv row.");
         }
         tokio::time::sleep_until(now + Duration::from_millis(1000)).await;
@@ -78,25 +83,77 @@ impl AccelFetcher {
     }
 }
 
+struct TapFetcher {
+    url: String,
+}
+
+impl TapFetcher {
+    pub fn new(addr: &str) -> TapFetcher {
+        TapFetcher {
+            url: format!("http://{addr}/taps"),
+        }
+    }
+
+    /// 前回から検出したタップ
+    pub async fn fetch(&self) -> Result<Vec<TapEvent>> {
+        let res = reqwest::get(&self.url)
+            .await
+            .context("Failed to fetch taps.")?
+            .json::<Vec<TapEvent>>()
+            .await
+            .context("Failed to parse taps.")?;
+        Ok(res)
+    }
+}
+
+/// 加速度の行か、検出したタップ1回ごとの行
 #[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
 struct CsvRow {
     pub ts: u128,
-    pub x: f64,
-    pub y: f64,
-    pub z: f64,
+    pub x: Option<f64>,
+    pub y: Option<f64>,
+    pub z: Option<f64>,
+    /// センサのタイムスタンプ [µs]
+    pub device_ts: Option<f64>,
+    pub tap: Option<TapKind>,
+    pub tap_axis: Option<TapAxis>,
+    pub tap_sign: Option<TapSign>,
 }
 
 impl CsvRow {
     pub fn now(accel: &Acceleration) -> Self {
         CsvRow {
             ts: ts(),
-            x: accel.x,
-            y: accel.y,
-            z: accel.z,
+            x: Some(accel.x),
+            y: Some(accel.y),
+            z: Some(accel.z),
+            device_ts: device_ts(accel.timestamp),
+            tap: None,
+            tap_axis: None,
+            tap_sign: None,
+        }
+    }
+
+    /// 加速度の列は空にして、検出したときのセンサのタイムスタンプを書く
+    pub fn tap(tap: &TapEvent) -> Self {
+        CsvRow {
+            ts: ts(),
+            x: None,
+            y: None,
+            z: None,
+            device_ts: device_ts(tap.timestamp),
+            tap: Some(tap.kind),
+            tap_axis: Some(tap.axis),
+            tap_sign: Some(tap.sign),
         }
     }
 }
 
+/// センサのタイムスタンプを [µs] にする
+fn device_ts(timestamp: Option<u32>) -> Option<f64> {
+    timestamp.map(|ts| ts as f64 * TIMESTAMP_RESOLUTION_US)
+}
+
 fn ts() -> u128 {
     UNIX_EPOCH.elapsed().unwrap().as_millis()
 }
@@ -0,0 +1,434 @@
+//! Implementation for [`AsyncRegisterBus`]
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use embedded_hal_async::delay::DelayNs;
+
+use super::*;
+
+/// [`Lsm6sdrx`] の非同期版
+pub struct Lsm6sdrxAsync<D> {
+    device: D,
+    config: Lsm6dsrxConfig,
+    compensation: Compensation,
+    verify_writes: bool,
+}
+
+impl<D> Lsm6sdrxAsync<D>
+where
+    D: AsyncRegisterBus,
+{
+    /// デフォルトの設定で初期化する
+    pub async fn new(
+        device: D,
+        delay: &mut impl DelayNs,
+    ) -> Result<Lsm6sdrxAsync<D>, Lsm6dsrxError<D::Error>> {
+        Self::with_config(device, Lsm6dsrxConfig::default(), delay).await
+    }
+
+    /// 設定を指定して初期化する
+    pub async fn with_config(
+        device: D,
+        config: Lsm6dsrxConfig,
+        delay: &mut impl DelayNs,
+    ) -> Result<Lsm6sdrxAsync<D>, Lsm6dsrxError<D::Error>> {
+        let mut imu = Lsm6sdrxAsync {
+            device,
+            config,
+            compensation: Compensation::default(),
+            verify_writes: false,
+        };
+
+        // check device
+        {
+            let who_am_i = read_reg_u8(&mut imu.device, RegisterAddress::WHO_AM_I).await?;
+            if who_am_i != DEFAULT_WHO_AM_I {
+                return Err(Lsm6dsrxError::WhoAmI(who_am_i));
+            }
+        }
+
+        imu.reset(delay).await?;
+
+        Ok(imu)
+    }
+
+    /// ソフトウェアリセットして、現在の設定を書き込み直す
+    ///
+    /// FIFO、割り込み、タップの設定はデフォルトに戻るので、必要なら [`Lsm6sdrxAsync::configure_fifo`],
+    /// [`Lsm6sdrxAsync::configure_interrupts`], [`Lsm6sdrxAsync::configure_tap`] し直すこと
+    pub async fn reset(&mut self, delay: &mut impl DelayNs) -> Result<(), Lsm6dsrxError<D::Error>> {
+        // reset device
+        // レジスタが初期値に戻るので、書き込んだ値は読み返して確認しない
+        {
+            let reg = self.read_register::<Ctrl3C>().await? | Ctrl3C::SW_RESET;
+            write_reg_u8(&mut self.device, RegisterAddress::CTRL3_C, reg.bits()).await?;
+        }
+        self.wait_for_clear(Ctrl3C::SW_RESET, SW_RESET_TIMEOUT_US, delay)
+            .await?;
+
+        self.init().await
+    }
+
+    /// メモリの内容 (トリミングパラメータ) を読み込み直してから [`Lsm6sdrxAsync::reset`] する
+    ///
+    /// 加速度計が動いている (`accel_odr` が `PowerDown` でない) 必要がある
+    pub async fn reboot_memory(
+        &mut self,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        // reboot memory content
+        // `BOOT` は自動でクリアされるので、書き込んだ値は読み返して確認しない
+        {
+            let reg = self.read_register::<Ctrl3C>().await? | Ctrl3C::BOOT;
+            write_reg_u8(&mut self.device, RegisterAddress::CTRL3_C, reg.bits()).await?;
+        }
+        delay.delay_ms(BOOT_TIME_MS).await;
+        self.wait_for_clear(Ctrl3C::BOOT, BOOT_TIMEOUT_US, delay)
+            .await?;
+
+        self.reset(delay).await
+    }
+
+    /// リセット後のレジスタに、ドライバが前提とする設定と現在の設定を書き込む
+    async fn init(&mut self) -> Result<(), Lsm6dsrxError<D::Error>> {
+        self.apply_writes(sequence::init(&self.config)).await
+    }
+
+    /// `CTRL3_C` の `flag` が 0 に戻るまで待つ
+    async fn wait_for_clear(
+        &mut self,
+        flag: Ctrl3C,
+        timeout_us: u32,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        let mut elapsed_us = 0;
+        loop {
+            let reg = self.read_register::<Ctrl3C>().await?;
+            if !reg.intersects(flag) {
+                return Ok(());
+            }
+            if elapsed_us >= timeout_us {
+                return Err(Lsm6dsrxError::ResetTimeout);
+            }
+            delay.delay_us(RESET_POLL_INTERVAL_US).await;
+            elapsed_us += RESET_POLL_INTERVAL_US;
+        }
+    }
+
+    /// ビットフラグの型を持つレジスタを読む
+    pub async fn read_register<R: Register>(&mut self) -> Result<R, Lsm6dsrxError<D::Error>> {
+        read_reg_u8(&mut self.device, R::ADDRESS)
+            .await
+            .map(R::from_bits_retain)
+    }
+
+    /// レジスタを読み、`f` で変更して書き込む
+    ///
+    /// [`Lsm6sdrxAsync::set_verify_writes`] で有効にした場合は書き込んだあとに読み返し、
+    /// 一致しなければ [`Lsm6dsrxError::VerifyFailed`] を返す
+    pub async fn modify<R: Register>(
+        &mut self,
+        f: impl FnOnce(&mut R),
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        let mut reg = self.read_register::<R>().await?;
+        f(&mut reg);
+        self.apply_write(RegisterWrite::set(R::ADDRESS, reg.bits()))
+            .await
+    }
+
+    /// [`sequence`] の書き込みを1つ行う
+    async fn apply_write(&mut self, write: RegisterWrite) -> Result<(), Lsm6dsrxError<D::Error>> {
+        if write.is_empty() {
+            return Ok(());
+        }
+        let current = if write.needs_read() {
+            read_reg_u8(&mut self.device, write.address).await?
+        } else {
+            0
+        };
+        let value = write.value(current);
+        write_reg_u8(&mut self.device, write.address, value).await?;
+
+        if self.verify_writes {
+            let actual = read_reg_u8(&mut self.device, write.address).await?;
+            verify_write(write.address, value, actual)?;
+        }
+
+        Ok(())
+    }
+
+    /// [`sequence`] の書き込みを順に行う
+    async fn apply_writes(
+        &mut self,
+        writes: impl IntoIterator<Item = RegisterWrite>,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        for write in writes {
+            self.apply_write(write).await?;
+        }
+        Ok(())
+    }
+
+    /// [`Lsm6sdrxAsync::modify`] で書き込んだ値を読み返して確認するかを設定する
+    pub fn set_verify_writes(&mut self, verify: bool) {
+        self.verify_writes = verify;
+    }
+
+    /// 現在の設定を返す
+    pub fn config(&self) -> &Lsm6dsrxConfig {
+        &self.config
+    }
+
+    /// 設定をデバイスに書き込む
+    pub async fn apply_config(
+        &mut self,
+        config: &Lsm6dsrxConfig,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        self.apply_writes(sequence::config(config)).await?;
+        self.config = config.clone();
+
+        Ok(())
+    }
+
+    /// FIFO を設定する
+    pub async fn configure_fifo(
+        &mut self,
+        config: &FifoConfig,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        self.apply_writes(sequence::fifo(config)?).await
+    }
+
+    /// FIFO の状態を取得する
+    pub async fn fifo_status(&mut self) -> Result<FifoStatus, Lsm6dsrxError<D::Error>> {
+        let [status1, status2] = self
+            .read_raw::<[u8; 2]>(RegisterAddress::FIFO_STATUS1)
+            .await?;
+        Ok(FifoStatus::from_registers(
+            status1,
+            FifoStatus2::from_bits_retain(status2),
+        ))
+    }
+
+    /// FIFO に溜まっているデータをすべて読み出し、1つずつ `f` に渡す
+    ///
+    /// 読み出したワード数を返す。FIFO が溢れていた場合は、残っていたデータを読み出したあとに
+    /// [`Lsm6dsrxError::FifoOverrun`] を返す
+    pub async fn drain_fifo_with<F>(&mut self, mut f: F) -> Result<usize, Lsm6dsrxError<D::Error>>
+    where
+        F: FnMut(FifoSample),
+    {
+        let mut drainer = FifoDrainer::new(self.fifo_status().await?, &self.config);
+        for _ in 0..drainer.unread() {
+            let word = self
+                .read_raw::<RawFifoWord>(RegisterAddress::FIFO_DATA_OUT_TAG)
+                .await?;
+            if let Some(sample) = drainer.sample(word, &mut self.compensation) {
+                f(sample);
+            }
+        }
+        drainer.finish()
+    }
+
+    /// FIFO に溜まっているデータをすべて読み出す
+    ///
+    /// FIFO が溢れていた場合も、読み出したデータは捨てずに [`FifoDrain::overrun`] で知らせる
+    #[cfg(feature = "alloc")]
+    pub async fn drain_fifo(&mut self) -> Result<FifoDrain, Lsm6dsrxError<D::Error>> {
+        let mut samples = Vec::new();
+        let result = self.drain_fifo_with(|sample| samples.push(sample)).await;
+        FifoDrain::new(samples, result)
+    }
+
+    /// 割り込みピンを設定する
+    ///
+    /// 組み込み機能の割り込みは、組み込み機能の側でも有効にしておく必要がある
+    pub async fn configure_interrupts(
+        &mut self,
+        config: &InterruptConfig,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        self.apply_writes(sequence::interrupts(config)?).await
+    }
+
+    /// 発生している割り込みの要因を読む
+    ///
+    /// latched の場合、読んだイベントはクリアされる
+    pub async fn interrupt_sources(&mut self) -> Result<InterruptSources, Lsm6dsrxError<D::Error>> {
+        let sources = self
+            .read_raw::<[u8; 5]>(RegisterAddress::ALL_INT_SRC)
+            .await?;
+        let mainpage = self
+            .read_raw::<[u8; 7]>(RegisterAddress::EMB_FUNC_STATUS_MAINPAGE)
+            .await?;
+        Ok(InterruptSources::from_registers(sources, mainpage))
+    }
+
+    /// タップの検出を設定する
+    ///
+    /// 検出したタップをピンに出すには [`Lsm6sdrxAsync::configure_interrupts`] で割り当てる
+    pub async fn configure_tap(
+        &mut self,
+        config: &TapConfig,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        self.apply_writes(sequence::tap(config)?).await
+    }
+
+    /// `TAP_SRC` を読んで、検出したタップを返す
+    ///
+    /// 検出していなければ `None` を返す。latched の場合、読んだイベントはクリアされる
+    pub async fn tap_event(&mut self) -> Result<Option<TapEvent>, Lsm6dsrxError<D::Error>> {
+        let source = read_reg_u8(&mut self.device, RegisterAddress::TAP_SRC)
+            .await
+            .map(TapSrc::from_bits_retain)?;
+        let timestamp = self.sample_timestamp().await?;
+        Ok(source.event(timestamp))
+    }
+
+    /// `STATUS_REG` を読む
+    pub async fn status(&mut self) -> Result<StatusReg, Lsm6dsrxError<D::Error>> {
+        read_reg_u8(&mut self.device, RegisterAddress::STATUS_REG)
+            .await
+            .map(StatusReg::from_bits_retain)
+    }
+
+    /// 新しい加速度が出力されるまで待つ
+    ///
+    /// `timeout_us` 待っても出力されなければ [`Lsm6dsrxError::DataReadyTimeout`] を返す
+    pub async fn wait_for_accel_ready(
+        &mut self,
+        timeout_us: u32,
+        delay: &mut impl DelayNs,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        let mut elapsed_us = 0;
+        loop {
+            if self.status().await?.contains(StatusReg::XLDA) {
+                return Ok(());
+            }
+            if elapsed_us >= timeout_us {
+                return Err(Lsm6dsrxError::DataReadyTimeout);
+            }
+            delay.delay_us(DATA_READY_POLL_INTERVAL_US).await;
+            elapsed_us += DATA_READY_POLL_INTERVAL_US;
+        }
+    }
+
+    /// 前回読んでから新しい加速度が出力されていれば取得する
+    ///
+    /// 出力されていなければ `None` を返す
+    pub async fn try_fetch_acceleration(
+        &mut self,
+    ) -> Result<Option<Acceleration>, Lsm6dsrxError<D::Error>> {
+        if !self.status().await?.contains(StatusReg::XLDA) {
+            return Ok(None);
+        }
+        self.fetch_acceleration().await.map(Some)
+    }
+
+    /// 加速度を取得する
+    ///
+    /// [`Lsm6sdrxAsync::accel_calibration`] で補正した値を返す
+    pub async fn fetch_acceleration(&mut self) -> Result<Acceleration, Lsm6dsrxError<D::Error>> {
+        let buffer = self.read_raw::<RawAxes>(RegisterAddress::OUTX_L_A).await?;
+        let timestamp = self.sample_timestamp().await?;
+        let acceleration = buffer.to_acceleration(self.config.accel_full_scale, timestamp);
+        Ok(self.compensation.acceleration(acceleration))
+    }
+
+    /// 現在の加速度計の補正を返す
+    pub fn accel_calibration(&self) -> &Calibration {
+        &self.compensation.accel_calibration
+    }
+
+    /// 加速度計の補正を設定する
+    ///
+    /// 以降に取得する加速度 (FIFO を含む) に適用する
+    pub fn set_accel_calibration(&mut self, calibration: Calibration) {
+        self.compensation.accel_calibration = calibration;
+    }
+
+    /// 現在のゼロレートバイアスと信頼度を返す
+    pub fn gyro_bias(&self) -> GyroBias {
+        self.compensation.gyro_bias.bias()
+    }
+
+    /// ゼロレートバイアスの推定器を返す
+    pub fn gyro_bias_estimator_mut(&mut self) -> &mut GyroBiasEstimator {
+        &mut self.compensation.gyro_bias
+    }
+
+    /// タイムスタンプカウンタを有効/無効にする
+    ///
+    /// 有効にすると、取得した加速度にタイムスタンプが付く。[`Lsm6dsrxConfig::timestamp`] を変更するので、リセット後も有効なまま
+    pub async fn enable_timestamp(&mut self, enable: bool) -> Result<(), Lsm6dsrxError<D::Error>> {
+        self.apply_write(sequence::timestamp(enable)).await?;
+        self.config.timestamp = enable;
+        Ok(())
+    }
+
+    /// タイムスタンプカウンタの値を取得する
+    ///
+    /// 1 LSB は [`schema::TIMESTAMP_RESOLUTION_US`] µs
+    pub async fn fetch_timestamp(&mut self) -> Result<u32, Lsm6dsrxError<D::Error>> {
+        self.read_raw::<u32>(RegisterAddress::TIMESTAMP0).await
+    }
+
+    /// タイムスタンプカウンタを 0 に戻す
+    pub async fn reset_timestamp(&mut self) -> Result<(), Lsm6dsrxError<D::Error>> {
+        write_reg_u8(
+            &mut self.device,
+            RegisterAddress::TIMESTAMP2,
+            TIMESTAMP_RESET,
+        )
+        .await
+    }
+
+    /// タイムスタンプカウンタが有効なら、出力レジスタを読んだ直後のタイムスタンプを返す
+    async fn sample_timestamp(&mut self) -> Result<Option<u32>, Lsm6dsrxError<D::Error>> {
+        if !self.config.timestamp {
+            return Ok(None);
+        }
+        self.fetch_timestamp().await.map(Some)
+    }
+
+    /// `addr` から連続したレジスタを `T` として読む
+    ///
+    /// `T` はレジスタの並びと一致する `#[repr(C, packed)]` な構造体であること
+    async fn read_raw<T: Default + Copy>(
+        &mut self,
+        addr: RegisterAddress,
+    ) -> Result<T, Lsm6dsrxError<D::Error>> {
+        let mut value = T::default();
+        let buffer = as_bytes_mut(&mut value);
+        self.device
+            .read_registers(addr, buffer)
+            .await
+            .map_err(Lsm6dsrxError::Bus)?;
+        debug!("buffer = {:?}", buffer);
+
+        Ok(value)
+    }
+}
+
+#[inline]
+async fn read_reg_u8<D: AsyncRegisterBus>(
+    device: &mut D,
+    addr: RegisterAddress,
+) -> Result<u8, Lsm6dsrxError<D::Error>> {
+    let mut read_buf = [u8::MIN];
+    device
+        .read_registers(addr, &mut read_buf)
+        .await
+        .map_err(Lsm6dsrxError::Bus)?;
+    Ok(read_buf[0])
+}
+
+#[inline]
+async fn write_reg_u8<D: AsyncRegisterBus>(
+    device: &mut D,
+    addr: RegisterAddress,
+    data: u8,
+) -> Result<(), Lsm6dsrxError<D::Error>> {
+    device
+        .write_register(addr, data)
+        .await
+        .map_err(Lsm6dsrxError::Bus)
+}
@@ -0,0 +1,180 @@
+//! 角速度計のゼロレートバイアスの推定
+//!
+//! 一定数のサンプルごとに加速度と角速度の分散を計算し、どちらも小さければ静止しているとみなして、
+//! その間の角速度の平均をバイアスとして取り込む
+
+use schema::{Acceleration, AngularRate};
+use serde::{Deserialize, Serialize};
+
+/// バイアス推定の設定
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(default)]
+pub struct GyroBiasConfig {
+    /// 分散を計算するサンプル数
+    pub window: u16,
+    /// 静止とみなす加速度の分散の最大値 (各軸) [mg^2]
+    pub accel_variance_max: f64,
+    /// 静止とみなす角速度の分散の最大値 (各軸) [mdps^2]
+    pub gyro_variance_max: f64,
+    /// 静止していた区間の平均を取り込む割合 (0 から 1)
+    pub smoothing: f64,
+    /// 信頼度が 1 になるまでに必要な静止区間の数
+    pub confident_windows: u16,
+}
+
+impl Default for GyroBiasConfig {
+    fn default() -> Self {
+        Self {
+            window: 50,
+            accel_variance_max: 100.0,
+            gyro_variance_max: 250_000.0,
+            smoothing: 0.1,
+            confident_windows: 10,
+        }
+    }
+}
+
+/// 推定したバイアス
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GyroBias {
+    /// [mdps]
+    pub x: f64,
+    /// [mdps]
+    pub y: f64,
+    /// [mdps]
+    pub z: f64,
+    /// 0 (まだ静止を検出していない) から 1 まで
+    pub confidence: f64,
+}
+
+/// 3軸の和と二乗和
+#[derive(Default, Debug, Clone, Copy)]
+struct AxesStats {
+    sum: [f64; 3],
+    sum_sq: [f64; 3],
+}
+
+impl AxesStats {
+    fn push(&mut self, values: [f64; 3]) {
+        for (i, value) in values.into_iter().enumerate() {
+            self.sum[i] += value;
+            self.sum_sq[i] += value * value;
+        }
+    }
+
+    fn mean(&self, count: f64) -> [f64; 3] {
+        [
+            self.sum[0] / count,
+            self.sum[1] / count,
+            self.sum[2] / count,
+        ]
+    }
+
+    /// 3軸の分散のうち最大のもの
+    fn max_variance(&self, count: f64) -> f64 {
+        let mean = self.mean(count);
+        (0..3)
+            .map(|i| self.sum_sq[i] / count - mean[i] * mean[i])
+            .fold(0.0, f64::max)
+    }
+}
+
+/// 角速度計のゼロレートバイアスを推定する
+#[derive(Debug, Clone)]
+pub struct GyroBiasEstimator {
+    config: GyroBiasConfig,
+    accel: AxesStats,
+    gyro: AxesStats,
+    count: u16,
+    bias: [f64; 3],
+    still_windows: u16,
+}
+
+impl Default for GyroBiasEstimator {
+    fn default() -> Self {
+        GyroBiasEstimator::new(GyroBiasConfig::default())
+    }
+}
+
+impl GyroBiasEstimator {
+    pub fn new(config: GyroBiasConfig) -> Self {
+        GyroBiasEstimator {
+            config,
+            accel: AxesStats::default(),
+            gyro: AxesStats::default(),
+            count: 0,
+            bias: [0.0; 3],
+            still_windows: 0,
+        }
+    }
+
+    /// 現在の設定を返す
+    pub fn config(&self) -> &GyroBiasConfig {
+        &self.config
+    }
+
+    /// 現在のバイアスと信頼度を返す
+    pub fn bias(&self) -> GyroBias {
+        let confident_windows = self.config.confident_windows.max(1);
+        GyroBias {
+            x: self.bias[0],
+            y: self.bias[1],
+            z: self.bias[2],
+            confidence: self.still_windows.min(confident_windows) as f64 / confident_windows as f64,
+        }
+    }
+
+    /// 推定をやり直す
+    pub fn reset(&mut self) {
+        *self = GyroBiasEstimator::new(self.config);
+    }
+
+    /// 同じ出力周期の加速度と (補正前の) 角速度を1組取り込む
+    ///
+    /// 区間が終わり、静止していてバイアスを更新した場合は `true` を返す
+    pub fn update(&mut self, acceleration: &Acceleration, angular_rate: &AngularRate) -> bool {
+        self.accel
+            .push([acceleration.x, acceleration.y, acceleration.z]);
+        self.gyro
+            .push([angular_rate.x, angular_rate.y, angular_rate.z]);
+        self.count += 1;
+        if self.count < self.config.window.max(2) {
+            return false;
+        }
+
+        let count = self.count as f64;
+        let still = self.accel.max_variance(count) <= self.config.accel_variance_max
+            && self.gyro.max_variance(count) <= self.config.gyro_variance_max;
+        if still {
+            let mean = self.gyro.mean(count);
+            // 最初の静止区間はそのまま使う
+            let smoothing = if self.still_windows == 0 {
+                1.0
+            } else {
+                self.config.smoothing
+            };
+            for (bias, mean) in self.bias.iter_mut().zip(mean) {
+                *bias += smoothing * (mean - *bias);
+            }
+            self.still_windows = self.still_windows.saturating_add(1);
+        }
+
+        self.accel = AxesStats::default();
+        self.gyro = AxesStats::default();
+        self.count = 0;
+
+        still
+    }
+
+    /// 角速度からバイアスを引く
+    pub fn compensate(&self, angular_rate: AngularRate) -> AngularRate {
+        AngularRate {
+            x: angular_rate.x - self.bias[0],
+            y: angular_rate.y - self.bias[1],
+            z: angular_rate.z - self.bias[2],
+            ..angular_rate
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! レジスタにアクセスするためのバス
+
+use embedded_hal::{
+    i2c::I2c,
+    spi::{Operation, SpiDevice},
+};
+
+use super::RegisterAddress;
+
+/// レジスタを読み書きするバス
+///
+/// 複数バイトの読み出しは `CTRL3_C` の `IF_INC` によるアドレスの自動インクリメントを前提とする
+pub trait RegisterBus {
+    type Error;
+
+    /// `addr` から `buf.len()` バイト読む
+    fn read_registers(&mut self, addr: RegisterAddress, buf: &mut [u8]) -> Result<(), Self::Error>;
+
+    /// `addr` に 1 バイト書き込む
+    fn write_register(&mut self, addr: RegisterAddress, data: u8) -> Result<(), Self::Error>;
+}
+
+/// レジスタを読み書きする非同期のバス
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncRegisterBus {
+    type Error;
+
+    /// `addr` から `buf.len()` バイト読む
+    async fn read_registers(
+        &mut self,
+        addr: RegisterAddress,
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error>;
+
+    /// `addr` に 1 バイト書き込む
+    async fn write_register(&mut self, addr: RegisterAddress, data: u8) -> Result<(), Self::Error>;
+}
+
+/// SPI で接続したセンサ
+pub struct SpiInterface<D> {
+    device: D,
+}
+
+impl<D> SpiInterface<D> {
+    pub fn new(device: D) -> Self {
+        SpiInterface { device }
+    }
+
+    /// SPI デバイスを返す
+    pub fn release(self) -> D {
+        self.device
+    }
+}
+
+impl<D: SpiDevice> RegisterBus for SpiInterface<D> {
+    type Error = D::Error;
+
+    fn read_registers(&mut self, addr: RegisterAddress, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.device
+            .transaction(&mut [Operation::Write(&[addr.read()]), Operation::Read(buf)])
+    }
+
+    fn write_register(&mut self, addr: RegisterAddress, data: u8) -> Result<(), Self::Error> {
+        self.device
+            .transaction(&mut [Operation::Write(&[addr.bits()]), Operation::Write(&[data])])
+    }
+}
+
+#[cfg(feature = "async")]
+impl<D: embedded_hal_async::spi::SpiDevice> AsyncRegisterBus for SpiInterface<D> {
+    type Error = D::Error;
+
+    async fn read_registers(
+        &mut self,
+        addr: RegisterAddress,
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.device
+            .transaction(&mut [Operation::Write(&[addr.read()]), Operation::Read(buf)])
+            .await
+    }
+
+    async fn write_register(&mut self, addr: RegisterAddress, data: u8) -> Result<(), Self::Error> {
+        self.device
+            .transaction(&mut [Operation::Write(&[addr.bits()]), Operation::Write(&[data])])
+            .await
+    }
+}
+
+/// SA0 ピンの状態で決まる I²C のスレーブアドレス
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SlaveAddress {
+    /// SA0 = GND (0x6A)
+    Sa0Low,
+    /// SA0 = VDD (0x6B)
+    #[default]
+    Sa0High,
+}
+
+impl SlaveAddress {
+    pub fn address(self) -> u8 {
+        match self {
+            SlaveAddress::Sa0Low => 0x6A,
+            SlaveAddress::Sa0High => 0x6B,
+        }
+    }
+}
+
+/// I²C で接続したセンサ
+pub struct I2cInterface<I> {
+    i2c: I,
+    address: u8,
+}
+
+impl<I> I2cInterface<I> {
+    pub fn new(i2c: I, address: SlaveAddress) -> Self {
+        I2cInterface {
+            i2c,
+            address: address.address(),
+        }
+    }
+
+    /// I²C バスを返す
+    pub fn release(self) -> I {
+        self.i2c
+    }
+}
+
+impl<I: I2c> RegisterBus for I2cInterface<I> {
+    type Error = I::Error;
+
+    fn read_registers(&mut self, addr: RegisterAddress, buf: &mut [u8]) -> Result<(), Self::Error> {
+        self.i2c.write_read(self.address, &[addr.bits()], buf)
+    }
+
+    fn write_register(&mut self, addr: RegisterAddress, data: u8) -> Result<(), Self::Error> {
+        self.i2c.write(self.address, &[addr.bits(), data])
+    }
+}
+
+#[cfg(feature = "async")]
+impl<I: embedded_hal_async::i2c::I2c> AsyncRegisterBus for I2cInterface<I> {
+    type Error = I::Error;
+
+    async fn read_registers(
+        &mut self,
+        addr: RegisterAddress,
+        buf: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.i2c.write_read(self.address, &[addr.bits()], buf).await
+    }
+
+    async fn write_register(&mut self, addr: RegisterAddress, data: u8) -> Result<(), Self::Error> {
+        self.i2c.write(self.address, &[addr.bits(), data]).await
+    }
+}
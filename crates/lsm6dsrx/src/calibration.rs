@@ -0,0 +1,165 @@
+//! 加速度計の6面キャリブレーション
+//!
+//! 各軸を上と下に向けた6つの姿勢で静止させたときの平均値から、感度のずれと軸間の傾きを打ち消す
+//! 補正行列と、ゼロ点のずれを求める
+
+use schema::Calibration;
+use serde::{Deserialize, Serialize};
+
+use super::STANDARD_GRAVITY;
+
+/// 補正行列を求められないとみなす行列式の大きさ
+const SINGULAR_DETERMINANT: f64 = 1e-9;
+
+/// 6面キャリブレーションの姿勢
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(rename_all = "kebab-case")]
+pub enum Orientation {
+    /// X 軸が上を向いている
+    XUp,
+    /// X 軸が下を向いている
+    XDown,
+    /// Y 軸が上を向いている
+    YUp,
+    /// Y 軸が下を向いている
+    YDown,
+    /// Z 軸が上を向いている
+    ZUp,
+    /// Z 軸が下を向いている
+    ZDown,
+}
+
+impl Orientation {
+    pub const ALL: [Orientation; 6] = [
+        Orientation::XUp,
+        Orientation::XDown,
+        Orientation::YUp,
+        Orientation::YDown,
+        Orientation::ZUp,
+        Orientation::ZDown,
+    ];
+
+    /// 名前 (`x-up` など)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Orientation::XUp => "x-up",
+            Orientation::XDown => "x-down",
+            Orientation::YUp => "y-up",
+            Orientation::YDown => "y-down",
+            Orientation::ZUp => "z-up",
+            Orientation::ZDown => "z-down",
+        }
+    }
+
+    /// この姿勢で静止しているときの理想的な加速度 [mg]
+    pub fn gravity(self) -> [f64; 3] {
+        let g = STANDARD_GRAVITY;
+        match self {
+            Orientation::XUp => [g, 0.0, 0.0],
+            Orientation::XDown => [-g, 0.0, 0.0],
+            Orientation::YUp => [0.0, g, 0.0],
+            Orientation::YDown => [0.0, -g, 0.0],
+            Orientation::ZUp => [0.0, 0.0, g],
+            Orientation::ZDown => [0.0, 0.0, -g],
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// 6つの姿勢で測った加速度の平均を集めて補正を求める
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SixPositionCalibration {
+    measurements: [Option<[f64; 3]>; 6],
+}
+
+impl SixPositionCalibration {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `orientation` で静止しているときの加速度の平均 [mg] を記録する
+    ///
+    /// 同じ姿勢を記録し直した場合は上書きする
+    pub fn record(&mut self, orientation: Orientation, mean: [f64; 3]) {
+        self.measurements[orientation.index()] = Some(mean);
+    }
+
+    /// `orientation` で記録した値を返す
+    pub fn measurement(&self, orientation: Orientation) -> Option<[f64; 3]> {
+        self.measurements[orientation.index()]
+    }
+
+    /// まだ記録していない姿勢
+    pub fn missing(&self) -> impl Iterator<Item = Orientation> + '_ {
+        Orientation::ALL
+            .into_iter()
+            .filter(|orientation| self.measurement(*orientation).is_none())
+    }
+
+    /// すべての姿勢を記録した
+    pub fn is_complete(&self) -> bool {
+        self.missing().next().is_none()
+    }
+
+    /// 補正を求める
+    ///
+    /// 記録していない姿勢がある場合や、測定値から補正行列を求められない場合は `None` を返す
+    pub fn solve(&self) -> Option<Calibration> {
+        let [Some(x_up), Some(x_down), Some(y_up), Some(y_down), Some(z_up), Some(z_down)] =
+            self.measurements
+        else {
+            return None;
+        };
+
+        // 上下の組で重力が打ち消し合うので、6つの平均がゼロ点のずれになる
+        let mut bias = [0.0; 3];
+        for i in 0..3 {
+            bias[i] = (x_up[i] + x_down[i] + y_up[i] + y_down[i] + z_up[i] + z_down[i]) / 6.0;
+        }
+
+        // 測定値 = sensitivity * 真の値 + bias とすると、sensitivity の j 列目は
+        // j 軸を上に向けたときと下に向けたときの差の半分を 1g で割ったもの
+        let mut sensitivity = [[0.0; 3]; 3];
+        for (j, (up, down)) in [(x_up, x_down), (y_up, y_down), (z_up, z_down)]
+            .into_iter()
+            .enumerate()
+        {
+            for i in 0..3 {
+                sensitivity[i][j] = (up[i] - down[i]) / (2.0 * STANDARD_GRAVITY);
+            }
+        }
+
+        let matrix = invert(sensitivity)?;
+        Some(Calibration { matrix, bias })
+    }
+}
+
+/// 3x3 行列の逆行列
+fn invert(m: [[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    // 余因子
+    let c =
+        |r0: usize, r1: usize, c0: usize, c1: usize| m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+    let cofactor = [
+        [c(1, 2, 1, 2), -c(1, 2, 0, 2), c(1, 2, 0, 1)],
+        [-c(0, 2, 1, 2), c(0, 2, 0, 2), -c(0, 2, 0, 1)],
+        [c(0, 1, 1, 2), -c(0, 1, 0, 2), c(0, 1, 0, 1)],
+    ];
+    let det = m[0][0] * cofactor[0][0] + m[0][1] * cofactor[0][1] + m[0][2] * cofactor[0][2];
+    // no_std では f64::abs が使えない
+    if -SINGULAR_DETERMINANT < det && det < SINGULAR_DETERMINANT {
+        return None;
+    }
+
+    // 逆行列は余因子行列の転置を行列式で割ったもの
+    let mut inverse = [[0.0; 3]; 3];
+    for (i, row) in inverse.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = cofactor[j][i] / det;
+        }
+    }
+    Some(inverse)
+}
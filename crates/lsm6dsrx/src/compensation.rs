@@ -0,0 +1,71 @@
+//! 変換した出力にかける補正
+//!
+//! 同期版と非同期版のドライバで同じ値を返すように、出力レジスタと FIFO から読んだ値はすべてここを通す
+
+use schema::{Acceleration, AngularRate, Calibration};
+
+use super::{FifoSample, GyroBiasEstimator};
+
+/// 加速度計の補正と角速度計のゼロレートバイアス
+#[derive(Default, Debug, Clone)]
+pub(super) struct Compensation {
+    pub accel_calibration: Calibration,
+    pub gyro_bias: GyroBiasEstimator,
+    /// 対になる角速度をまだ読んでいない、FIFO の (補正後の) 加速度
+    fifo_acceleration: Option<Acceleration>,
+    /// 対になる加速度をまだ読んでいない、FIFO の (補正前の) 角速度
+    fifo_angular_rate: Option<AngularRate>,
+}
+
+impl Compensation {
+    /// 加速度計の補正をかける
+    pub fn acceleration(&self, acceleration: Acceleration) -> Acceleration {
+        self.accel_calibration.apply(acceleration)
+    }
+
+    /// 推定したゼロレートバイアスを引く
+    pub fn angular_rate(&self, angular_rate: AngularRate) -> AngularRate {
+        self.gyro_bias.compensate(angular_rate)
+    }
+
+    /// 同じ出力周期の加速度と (補正前の) 角速度でバイアスの推定を更新してから、両方を補正する
+    pub fn motion(
+        &mut self,
+        acceleration: Acceleration,
+        angular_rate: AngularRate,
+    ) -> (Acceleration, AngularRate) {
+        let acceleration = self.acceleration(acceleration);
+        self.update_gyro_bias(&acceleration, &angular_rate);
+        (acceleration, self.angular_rate(angular_rate))
+    }
+
+    /// FIFO から読んだ加速度、角速度を補正する
+    ///
+    /// 加速度と角速度は別々のワードで来るので、続けて読んだ1組でバイアスの推定を更新する
+    pub fn fifo_sample(&mut self, sample: FifoSample) -> FifoSample {
+        match sample {
+            FifoSample::Acceleration(acceleration) => {
+                let acceleration = self.acceleration(acceleration);
+                match self.fifo_angular_rate.take() {
+                    Some(angular_rate) => self.update_gyro_bias(&acceleration, &angular_rate),
+                    None => self.fifo_acceleration = Some(acceleration.clone()),
+                }
+                FifoSample::Acceleration(acceleration)
+            }
+            FifoSample::AngularRate(angular_rate) => {
+                match self.fifo_acceleration.take() {
+                    Some(acceleration) => self.update_gyro_bias(&acceleration, &angular_rate),
+                    None => self.fifo_angular_rate = Some(angular_rate.clone()),
+                }
+                FifoSample::AngularRate(self.angular_rate(angular_rate))
+            }
+            sample => sample,
+        }
+    }
+
+    fn update_gyro_bias(&mut self, acceleration: &Acceleration, angular_rate: &AngularRate) {
+        if self.gyro_bias.update(acceleration, angular_rate) {
+            debug!("Updated gyro bias: {:?}", self.gyro_bias.bias());
+        }
+    }
+}
@@ -0,0 +1,618 @@
+//! センサの設定
+
+use serde::{Deserialize, Serialize};
+
+use super::{Ctrl1Xl, Ctrl2G, Ctrl6C, Ctrl8Xl};
+
+/// 加速度計のフルスケール
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AccelFullScale {
+    /// ±2g
+    #[default]
+    G2,
+    /// ±4g
+    G4,
+    /// ±8g
+    G8,
+    /// ±16g
+    G16,
+}
+
+impl AccelFullScale {
+    pub(super) const MASK: Ctrl1Xl = Ctrl1Xl::FS1_XL.union(Ctrl1Xl::FS0_XL);
+
+    /// `CTRL1_XL` の `FS1_XL`, `FS0_XL` に設定する値
+    pub(super) fn bits(self) -> Ctrl1Xl {
+        match self {
+            AccelFullScale::G2 => Ctrl1Xl::empty(),
+            AccelFullScale::G4 => Ctrl1Xl::FS1_XL,
+            AccelFullScale::G8 => Ctrl1Xl::FS1_XL | Ctrl1Xl::FS0_XL,
+            AccelFullScale::G16 => Ctrl1Xl::FS0_XL,
+        }
+    }
+
+    /// `CTRL1_XL` の `FS1_XL`, `FS0_XL` から読む
+    pub(super) fn from_bits(reg: Ctrl1Xl) -> Self {
+        match reg.intersection(Self::MASK).bits() >> 2 {
+            0b00 => AccelFullScale::G2,
+            0b10 => AccelFullScale::G4,
+            0b11 => AccelFullScale::G8,
+            _ => AccelFullScale::G16,
+        }
+    }
+
+    /// Linear acceleration sensitivity [mg/LSB]
+    pub fn sensitivity(self) -> f64 {
+        match self {
+            AccelFullScale::G2 => 0.061,
+            AccelFullScale::G4 => 0.122,
+            AccelFullScale::G8 => 0.244,
+            AccelFullScale::G16 => 0.488,
+        }
+    }
+}
+
+/// 角速度計のフルスケール
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GyroFullScale {
+    /// ±125dps
+    Dps125,
+    /// ±250dps
+    Dps250,
+    /// ±500dps
+    Dps500,
+    /// ±1000dps
+    Dps1000,
+    /// ±2000dps
+    #[default]
+    Dps2000,
+    /// ±4000dps
+    Dps4000,
+}
+
+impl GyroFullScale {
+    pub(super) const MASK: Ctrl2G = Ctrl2G::FS1_G
+        .union(Ctrl2G::FS0_G)
+        .union(Ctrl2G::FS_125)
+        .union(Ctrl2G::FS_4000);
+
+    /// `CTRL2_G` の `FS1_G`, `FS0_G`, `FS_125`, `FS_4000` に設定する値
+    pub(super) fn bits(self) -> Ctrl2G {
+        match self {
+            GyroFullScale::Dps125 => Ctrl2G::FS_125,
+            GyroFullScale::Dps250 => Ctrl2G::empty(),
+            GyroFullScale::Dps500 => Ctrl2G::FS0_G,
+            GyroFullScale::Dps1000 => Ctrl2G::FS1_G,
+            GyroFullScale::Dps2000 => Ctrl2G::FS1_G | Ctrl2G::FS0_G,
+            GyroFullScale::Dps4000 => Ctrl2G::FS_4000,
+        }
+    }
+
+    /// `CTRL2_G` の `FS1_G`, `FS0_G`, `FS_125`, `FS_4000` から読む
+    pub(super) fn from_bits(reg: Ctrl2G) -> Option<Self> {
+        match reg.intersection(Self::MASK).bits() {
+            0b0010 => Some(GyroFullScale::Dps125),
+            0b0000 => Some(GyroFullScale::Dps250),
+            0b0100 => Some(GyroFullScale::Dps500),
+            0b1000 => Some(GyroFullScale::Dps1000),
+            0b1100 => Some(GyroFullScale::Dps2000),
+            0b0001 => Some(GyroFullScale::Dps4000),
+            _ => None,
+        }
+    }
+
+    /// Angular rate sensitivity [mdps/LSB]
+    pub fn sensitivity(self) -> f64 {
+        match self {
+            GyroFullScale::Dps125 => 4.375,
+            GyroFullScale::Dps250 => 8.75,
+            GyroFullScale::Dps500 => 17.5,
+            GyroFullScale::Dps1000 => 35.0,
+            GyroFullScale::Dps2000 => 70.0,
+            GyroFullScale::Dps4000 => 140.0,
+        }
+    }
+}
+
+/// 加速度計の出力レート
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AccelOdr {
+    /// Power-down
+    PowerDown,
+    /// 1.6Hz (low power only, [`PowerMode::HighPerformance`] では 12.5Hz になる)
+    Hz1_6,
+    /// 12.5Hz
+    Hz12_5,
+    /// 26Hz
+    Hz26,
+    /// 52Hz
+    Hz52,
+    /// 104Hz
+    Hz104,
+    /// 208Hz
+    Hz208,
+    /// 416Hz
+    Hz416,
+    /// 833Hz
+    Hz833,
+    /// 1.66kHz
+    #[default]
+    Hz1660,
+    /// 3.33kHz
+    Hz3330,
+    /// 6.66kHz
+    Hz6660,
+}
+
+impl AccelOdr {
+    pub(super) const MASK: Ctrl1Xl = Ctrl1Xl::ODR_XL3
+        .union(Ctrl1Xl::ODR_XL2)
+        .union(Ctrl1Xl::ODR_XL1)
+        .union(Ctrl1Xl::ODR_XL0);
+
+    /// `CTRL1_XL` の `ODR_XL[3:0]` に設定する値
+    pub(super) fn bits(self) -> Ctrl1Xl {
+        let odr: u8 = match self {
+            AccelOdr::PowerDown => 0b0000,
+            AccelOdr::Hz1_6 => 0b1011,
+            AccelOdr::Hz12_5 => 0b0001,
+            AccelOdr::Hz26 => 0b0010,
+            AccelOdr::Hz52 => 0b0011,
+            AccelOdr::Hz104 => 0b0100,
+            AccelOdr::Hz208 => 0b0101,
+            AccelOdr::Hz416 => 0b0110,
+            AccelOdr::Hz833 => 0b0111,
+            AccelOdr::Hz1660 => 0b1000,
+            AccelOdr::Hz3330 => 0b1001,
+            AccelOdr::Hz6660 => 0b1010,
+        };
+        Ctrl1Xl::from_bits_retain(odr << 4)
+    }
+
+    /// `CTRL1_XL` の `ODR_XL[3:0]` から読む
+    pub(super) fn from_bits(reg: Ctrl1Xl) -> Option<Self> {
+        match reg.intersection(Self::MASK).bits() >> 4 {
+            0b0000 => Some(AccelOdr::PowerDown),
+            0b1011 => Some(AccelOdr::Hz1_6),
+            0b0001 => Some(AccelOdr::Hz12_5),
+            0b0010 => Some(AccelOdr::Hz26),
+            0b0011 => Some(AccelOdr::Hz52),
+            0b0100 => Some(AccelOdr::Hz104),
+            0b0101 => Some(AccelOdr::Hz208),
+            0b0110 => Some(AccelOdr::Hz416),
+            0b0111 => Some(AccelOdr::Hz833),
+            0b1000 => Some(AccelOdr::Hz1660),
+            0b1001 => Some(AccelOdr::Hz3330),
+            0b1010 => Some(AccelOdr::Hz6660),
+            _ => None,
+        }
+    }
+
+    /// 出力レート [Hz] (power-down のときは `None`)
+    pub fn frequency(self) -> Option<f64> {
+        match self {
+            AccelOdr::PowerDown => None,
+            AccelOdr::Hz1_6 => Some(1.6),
+            AccelOdr::Hz12_5 => Some(12.5),
+            AccelOdr::Hz26 => Some(26.0),
+            AccelOdr::Hz52 => Some(52.0),
+            AccelOdr::Hz104 => Some(104.0),
+            AccelOdr::Hz208 => Some(208.0),
+            AccelOdr::Hz416 => Some(416.0),
+            AccelOdr::Hz833 => Some(833.0),
+            AccelOdr::Hz1660 => Some(1660.0),
+            AccelOdr::Hz3330 => Some(3330.0),
+            AccelOdr::Hz6660 => Some(6660.0),
+        }
+    }
+}
+
+/// 角速度計の出力レート
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GyroOdr {
+    /// Power-down
+    PowerDown,
+    /// 12.5Hz
+    Hz12_5,
+    /// 26Hz
+    Hz26,
+    /// 52Hz
+    Hz52,
+    /// 104Hz
+    Hz104,
+    /// 208Hz
+    Hz208,
+    /// 416Hz
+    Hz416,
+    /// 833Hz
+    Hz833,
+    /// 1.66kHz
+    #[default]
+    Hz1660,
+    /// 3.33kHz
+    Hz3330,
+    /// 6.66kHz
+    Hz6660,
+}
+
+impl GyroOdr {
+    pub(super) const MASK: Ctrl2G = Ctrl2G::ODR_G3
+        .union(Ctrl2G::ODR_G2)
+        .union(Ctrl2G::ODR_G1)
+        .union(Ctrl2G::ODR_G0);
+
+    /// `CTRL2_G` の `ODR_G[3:0]` に設定する値
+    pub(super) fn bits(self) -> Ctrl2G {
+        let odr: u8 = match self {
+            GyroOdr::PowerDown => 0b0000,
+            GyroOdr::Hz12_5 => 0b0001,
+            GyroOdr::Hz26 => 0b0010,
+            GyroOdr::Hz52 => 0b0011,
+            GyroOdr::Hz104 => 0b0100,
+            GyroOdr::Hz208 => 0b0101,
+            GyroOdr::Hz416 => 0b0110,
+            GyroOdr::Hz833 => 0b0111,
+            GyroOdr::Hz1660 => 0b1000,
+            GyroOdr::Hz3330 => 0b1001,
+            GyroOdr::Hz6660 => 0b1010,
+        };
+        Ctrl2G::from_bits_retain(odr << 4)
+    }
+
+    /// `CTRL2_G` の `ODR_G[3:0]` から読む
+    pub(super) fn from_bits(reg: Ctrl2G) -> Option<Self> {
+        match reg.intersection(Self::MASK).bits() >> 4 {
+            0b0000 => Some(GyroOdr::PowerDown),
+            0b0001 => Some(GyroOdr::Hz12_5),
+            0b0010 => Some(GyroOdr::Hz26),
+            0b0011 => Some(GyroOdr::Hz52),
+            0b0100 => Some(GyroOdr::Hz104),
+            0b0101 => Some(GyroOdr::Hz208),
+            0b0110 => Some(GyroOdr::Hz416),
+            0b0111 => Some(GyroOdr::Hz833),
+            0b1000 => Some(GyroOdr::Hz1660),
+            0b1001 => Some(GyroOdr::Hz3330),
+            0b1010 => Some(GyroOdr::Hz6660),
+            _ => None,
+        }
+    }
+
+    /// 出力レート [Hz] (power-down のときは `None`)
+    pub fn frequency(self) -> Option<f64> {
+        match self {
+            GyroOdr::PowerDown => None,
+            GyroOdr::Hz12_5 => Some(12.5),
+            GyroOdr::Hz26 => Some(26.0),
+            GyroOdr::Hz52 => Some(52.0),
+            GyroOdr::Hz104 => Some(104.0),
+            GyroOdr::Hz208 => Some(208.0),
+            GyroOdr::Hz416 => Some(416.0),
+            GyroOdr::Hz833 => Some(833.0),
+            GyroOdr::Hz1660 => Some(1660.0),
+            GyroOdr::Hz3330 => Some(3330.0),
+            GyroOdr::Hz6660 => Some(6660.0),
+        }
+    }
+}
+
+/// 動作モード (`XL_HM_MODE`, `G_HM_MODE`)
+///
+/// [`PowerMode::LowPowerNormal`] のとき、実際のモードは出力レートで決まる
+/// - 52Hz 以下: low-power mode
+/// - 104Hz, 208Hz: normal mode
+/// - 416Hz 以上: high-performance mode
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerMode {
+    /// High-performance mode
+    #[default]
+    HighPerformance,
+    /// Low-power / normal mode
+    LowPowerNormal,
+}
+
+/// 加速度計のフィルタのカットオフ周波数 (`HPCF_XL[2:0]`)
+///
+/// LPF2 が有効なときは LPF2 の、HP フィルタを使うときは HP フィルタのカットオフになる
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AccelFilterCutoff {
+    /// ODR/4
+    OdrDiv4,
+    /// ODR/10
+    #[default]
+    OdrDiv10,
+    /// ODR/20
+    OdrDiv20,
+    /// ODR/45
+    OdrDiv45,
+    /// ODR/100
+    OdrDiv100,
+    /// ODR/200
+    OdrDiv200,
+    /// ODR/400
+    OdrDiv400,
+    /// ODR/800
+    OdrDiv800,
+}
+
+impl AccelFilterCutoff {
+    pub(super) const MASK: Ctrl8Xl = Ctrl8Xl::HPCF_XL_2
+        .union(Ctrl8Xl::HPCF_XL_1)
+        .union(Ctrl8Xl::HPCF_XL_0);
+
+    /// `CTRL8_XL` の `HPCF_XL[2:0]` に設定する値
+    pub(super) fn bits(self) -> Ctrl8Xl {
+        let hpcf: u8 = match self {
+            AccelFilterCutoff::OdrDiv4 => 0b000,
+            AccelFilterCutoff::OdrDiv10 => 0b001,
+            AccelFilterCutoff::OdrDiv20 => 0b010,
+            AccelFilterCutoff::OdrDiv45 => 0b011,
+            AccelFilterCutoff::OdrDiv100 => 0b100,
+            AccelFilterCutoff::OdrDiv200 => 0b101,
+            AccelFilterCutoff::OdrDiv400 => 0b110,
+            AccelFilterCutoff::OdrDiv800 => 0b111,
+        };
+        Ctrl8Xl::from_bits_retain(hpcf << 5)
+    }
+
+    /// `CTRL8_XL` の `HPCF_XL_[2:0]` から読む
+    pub(super) fn from_bits(reg: Ctrl8Xl) -> Self {
+        match reg.intersection(Self::MASK).bits() >> 5 {
+            0b000 => AccelFilterCutoff::OdrDiv4,
+            0b001 => AccelFilterCutoff::OdrDiv10,
+            0b010 => AccelFilterCutoff::OdrDiv20,
+            0b011 => AccelFilterCutoff::OdrDiv45,
+            0b100 => AccelFilterCutoff::OdrDiv100,
+            0b101 => AccelFilterCutoff::OdrDiv200,
+            0b110 => AccelFilterCutoff::OdrDiv400,
+            _ => AccelFilterCutoff::OdrDiv800,
+        }
+    }
+}
+
+/// 加速度計のユーザーオフセットの重み (`USR_OFF_W`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OffsetWeight {
+    /// 2^-10 g/LSB
+    #[default]
+    Pow2Minus10,
+    /// 2^-6 g/LSB
+    Pow2Minus6,
+}
+
+impl OffsetWeight {
+    /// [mg/LSB]
+    pub fn sensitivity(self) -> f64 {
+        match self {
+            OffsetWeight::Pow2Minus10 => 1000.0 / 1024.0,
+            OffsetWeight::Pow2Minus6 => 1000.0 / 64.0,
+        }
+    }
+}
+
+/// 加速度計のユーザーオフセット (`X_OFS_USR`, `Y_OFS_USR`, `Z_OFS_USR`)
+///
+/// 出力からそれぞれ `値 × weight` が引かれる
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AccelUserOffset {
+    pub x: i8,
+    pub y: i8,
+    pub z: i8,
+}
+
+impl AccelUserOffset {
+    pub fn new(x: i8, y: i8, z: i8) -> Self {
+        AccelUserOffset { x, y, z }
+    }
+
+    /// 各軸のずれ [mg] を打ち消すオフセットを計算する
+    ///
+    /// レジスタの範囲 (-127 から 127) に収まらない軸があれば `None` を返す
+    pub fn from_bias(bias: [f64; 3], weight: OffsetWeight) -> Option<Self> {
+        let to_register = |bias: f64| {
+            let value = bias / weight.sensitivity();
+            // no_std では f64::round が使えないので、0 から遠い方に丸める
+            let value = if value < 0.0 {
+                value - 0.5
+            } else {
+                value + 0.5
+            };
+            if -128.0 < value && value < 128.0 {
+                Some(value as i8)
+            } else {
+                None
+            }
+        };
+        Some(AccelUserOffset {
+            x: to_register(bias[0])?,
+            y: to_register(bias[1])?,
+            z: to_register(bias[2])?,
+        })
+    }
+
+    /// 出力から引かれる値 [mg]
+    pub fn to_bias(self, weight: OffsetWeight) -> [f64; 3] {
+        [
+            self.x as f64 * weight.sensitivity(),
+            self.y as f64 * weight.sensitivity(),
+            self.z as f64 * weight.sensitivity(),
+        ]
+    }
+}
+
+/// 角速度計の LPF1 の帯域 (`FTYPE[2:0]`)
+///
+/// 実際のカットオフ周波数は ODR によって変わる
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GyroLpf1Bandwidth {
+    Ftype0,
+    Ftype1,
+    #[default]
+    Ftype2,
+    Ftype3,
+    Ftype4,
+    Ftype5,
+    Ftype6,
+    Ftype7,
+}
+
+impl GyroLpf1Bandwidth {
+    pub(super) const MASK: Ctrl6C = Ctrl6C::FTYPE_2
+        .union(Ctrl6C::FTYPE_1)
+        .union(Ctrl6C::FTYPE_0);
+
+    /// `CTRL6_C` の `FTYPE[2:0]` に設定する値
+    pub(super) fn bits(self) -> Ctrl6C {
+        let ftype: u8 = match self {
+            GyroLpf1Bandwidth::Ftype0 => 0b000,
+            GyroLpf1Bandwidth::Ftype1 => 0b001,
+            GyroLpf1Bandwidth::Ftype2 => 0b010,
+            GyroLpf1Bandwidth::Ftype3 => 0b011,
+            GyroLpf1Bandwidth::Ftype4 => 0b100,
+            GyroLpf1Bandwidth::Ftype5 => 0b101,
+            GyroLpf1Bandwidth::Ftype6 => 0b110,
+            GyroLpf1Bandwidth::Ftype7 => 0b111,
+        };
+        Ctrl6C::from_bits_retain(ftype)
+    }
+
+    /// `CTRL6_C` の `FTYPE[2:0]` から読む
+    pub(super) fn from_bits(reg: Ctrl6C) -> Self {
+        match reg.intersection(Self::MASK).bits() {
+            0b000 => GyroLpf1Bandwidth::Ftype0,
+            0b001 => GyroLpf1Bandwidth::Ftype1,
+            0b010 => GyroLpf1Bandwidth::Ftype2,
+            0b011 => GyroLpf1Bandwidth::Ftype3,
+            0b100 => GyroLpf1Bandwidth::Ftype4,
+            0b101 => GyroLpf1Bandwidth::Ftype5,
+            0b110 => GyroLpf1Bandwidth::Ftype6,
+            _ => GyroLpf1Bandwidth::Ftype7,
+        }
+    }
+}
+
+/// [`Lsm6sdrx`](super::Lsm6sdrx) の設定
+///
+/// デフォルト値は
+/// - 加速度計: 1.66kHz, high-performance, ±2g, LPF2 有効, ODR/10
+/// - オフセットの重み: 2^-10 g/LSB, オフセット: 0
+/// - 角速度計: 1.66kHz, high-performance, ±2000dps, LPF1 有効, `FTYPE` = 010
+/// - タイムスタンプカウンタ: 無効
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(default)]
+pub struct Lsm6dsrxConfig {
+    pub accel_odr: AccelOdr,
+    pub accel_power_mode: PowerMode,
+    pub accel_full_scale: AccelFullScale,
+    pub accel_lpf2: bool,
+    pub accel_filter_cutoff: AccelFilterCutoff,
+    pub accel_offset_weight: OffsetWeight,
+    pub accel_user_offset: AccelUserOffset,
+    pub gyro_odr: GyroOdr,
+    pub gyro_power_mode: PowerMode,
+    pub gyro_full_scale: GyroFullScale,
+    pub gyro_lpf1: bool,
+    pub gyro_lpf1_bandwidth: GyroLpf1Bandwidth,
+    /// タイムスタンプカウンタを有効にして、取得した値にタイムスタンプを付ける
+    pub timestamp: bool,
+}
+
+impl Default for Lsm6dsrxConfig {
+    fn default() -> Self {
+        Self {
+            accel_odr: AccelOdr::default(),
+            accel_power_mode: PowerMode::default(),
+            accel_full_scale: AccelFullScale::default(),
+            accel_lpf2: true,
+            accel_filter_cutoff: AccelFilterCutoff::default(),
+            accel_offset_weight: OffsetWeight::default(),
+            accel_user_offset: AccelUserOffset::default(),
+            gyro_odr: GyroOdr::default(),
+            gyro_power_mode: PowerMode::default(),
+            gyro_full_scale: GyroFullScale::default(),
+            gyro_lpf1: true,
+            gyro_lpf1_bandwidth: GyroLpf1Bandwidth::default(),
+            timestamp: false,
+        }
+    }
+}
+
+impl Lsm6dsrxConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn accel_odr(mut self, accel_odr: AccelOdr) -> Self {
+        self.accel_odr = accel_odr;
+        self
+    }
+
+    pub fn accel_power_mode(mut self, accel_power_mode: PowerMode) -> Self {
+        self.accel_power_mode = accel_power_mode;
+        self
+    }
+
+    pub fn accel_full_scale(mut self, accel_full_scale: AccelFullScale) -> Self {
+        self.accel_full_scale = accel_full_scale;
+        self
+    }
+
+    pub fn accel_lpf2(mut self, accel_lpf2: bool) -> Self {
+        self.accel_lpf2 = accel_lpf2;
+        self
+    }
+
+    pub fn accel_filter_cutoff(mut self, accel_filter_cutoff: AccelFilterCutoff) -> Self {
+        self.accel_filter_cutoff = accel_filter_cutoff;
+        self
+    }
+
+    pub fn accel_offset_weight(mut self, accel_offset_weight: OffsetWeight) -> Self {
+        self.accel_offset_weight = accel_offset_weight;
+        self
+    }
+
+    pub fn accel_user_offset(mut self, accel_user_offset: AccelUserOffset) -> Self {
+        self.accel_user_offset = accel_user_offset;
+        self
+    }
+
+    pub fn gyro_odr(mut self, gyro_odr: GyroOdr) -> Self {
+        self.gyro_odr = gyro_odr;
+        self
+    }
+
+    pub fn gyro_power_mode(mut self, gyro_power_mode: PowerMode) -> Self {
+        self.gyro_power_mode = gyro_power_mode;
+        self
+    }
+
+    pub fn gyro_full_scale(mut self, gyro_full_scale: GyroFullScale) -> Self {
+        self.gyro_full_scale = gyro_full_scale;
+        self
+    }
+
+    pub fn gyro_lpf1(mut self, gyro_lpf1: bool) -> Self {
+        self.gyro_lpf1 = gyro_lpf1;
+        self
+    }
+
+    pub fn gyro_lpf1_bandwidth(mut self, gyro_lpf1_bandwidth: GyroLpf1Bandwidth) -> Self {
+        self.gyro_lpf1_bandwidth = gyro_lpf1_bandwidth;
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: bool) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+}
@@ -0,0 +1,156 @@
+//! レジスタのダンプ
+//!
+//! 設定が意図どおりに書き込まれているかを基板ごとに比べるために、すべてのレジスタを読む
+
+use serde::Serialize;
+
+use super::{
+    CounterBdrReg1, Ctrl10C, Ctrl1Xl, Ctrl2G, Ctrl3C, Ctrl4C, Ctrl5C, Ctrl6C, Ctrl7G, Ctrl8Xl,
+    Ctrl9Xl, EmbFuncStatusMainpage, FifoCtrl2, FifoCtrl3, FifoCtrl4, FifoStatus2, Int1Ctrl,
+    Int2Ctrl, IntDur2, Md1Cfg, Md2Cfg, RegisterAddress, StatusReg, TapCfg0, TapCfg1, TapCfg2,
+    TapThs6d, WakeUpThs,
+};
+
+/// ダンプで読むレジスタか
+///
+/// `FIFO_DATA_OUT_*` は読むと FIFO から取り出してしまい、`*_SRC` は読むとラッチした割り込みが
+/// クリアされてしまうので読まない
+pub(super) fn is_dumpable(address: RegisterAddress) -> bool {
+    let fifo_out =
+        RegisterAddress::FIFO_DATA_OUT_TAG.bits()..=RegisterAddress::FIFO_DATA_OUT_Z_H.bits();
+    let sources = RegisterAddress::ALL_INT_SRC.bits()..=RegisterAddress::D6D_SRC.bits();
+    !fifo_out.contains(&address.bits()) && !sources.contains(&address.bits())
+}
+
+/// レジスタの値
+///
+/// ビットフラグの型があるレジスタはデコードし、ないものはそのままの値を持つ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RegisterValue {
+    FifoCtrl2(FifoCtrl2),
+    FifoCtrl3(FifoCtrl3),
+    FifoCtrl4(FifoCtrl4),
+    CounterBdrReg1(CounterBdrReg1),
+    Int1Ctrl(Int1Ctrl),
+    Int2Ctrl(Int2Ctrl),
+    Ctrl1Xl(Ctrl1Xl),
+    Ctrl2G(Ctrl2G),
+    Ctrl3C(Ctrl3C),
+    Ctrl4C(Ctrl4C),
+    Ctrl5C(Ctrl5C),
+    Ctrl6C(Ctrl6C),
+    Ctrl7G(Ctrl7G),
+    Ctrl8Xl(Ctrl8Xl),
+    Ctrl9Xl(Ctrl9Xl),
+    Ctrl10C(Ctrl10C),
+    StatusReg(StatusReg),
+    EmbFuncStatusMainpage(EmbFuncStatusMainpage),
+    FifoStatus2(FifoStatus2),
+    TapCfg0(TapCfg0),
+    TapCfg1(TapCfg1),
+    TapCfg2(TapCfg2),
+    TapThs6d(TapThs6d),
+    IntDur2(IntDur2),
+    WakeUpThs(WakeUpThs),
+    Md1Cfg(Md1Cfg),
+    Md2Cfg(Md2Cfg),
+    Raw(u8),
+}
+
+impl RegisterValue {
+    /// `address` から読んだ値をデコードする
+    pub fn decode(address: RegisterAddress, value: u8) -> Self {
+        match address {
+            RegisterAddress::FIFO_CTRL2 => Self::FifoCtrl2(FifoCtrl2::from_bits_retain(value)),
+            RegisterAddress::FIFO_CTRL3 => Self::FifoCtrl3(FifoCtrl3::from_bits_retain(value)),
+            RegisterAddress::FIFO_CTRL4 => Self::FifoCtrl4(FifoCtrl4::from_bits_retain(value)),
+            RegisterAddress::COUNTER_BDR_REG1 => {
+                Self::CounterBdrReg1(CounterBdrReg1::from_bits_retain(value))
+            }
+            RegisterAddress::INT1_CTRL => Self::Int1Ctrl(Int1Ctrl::from_bits_retain(value)),
+            RegisterAddress::INT2_CTRL => Self::Int2Ctrl(Int2Ctrl::from_bits_retain(value)),
+            RegisterAddress::CTRL1_XL => Self::Ctrl1Xl(Ctrl1Xl::from_bits_retain(value)),
+            RegisterAddress::CTRL2_G => Self::Ctrl2G(Ctrl2G::from_bits_retain(value)),
+            RegisterAddress::CTRL3_C => Self::Ctrl3C(Ctrl3C::from_bits_retain(value)),
+            RegisterAddress::CTRL4_C => Self::Ctrl4C(Ctrl4C::from_bits_retain(value)),
+            RegisterAddress::CTRL5_C => Self::Ctrl5C(Ctrl5C::from_bits_retain(value)),
+            RegisterAddress::CTRL6_C => Self::Ctrl6C(Ctrl6C::from_bits_retain(value)),
+            RegisterAddress::CTRL7_G => Self::Ctrl7G(Ctrl7G::from_bits_retain(value)),
+            RegisterAddress::CTRL8_XL => Self::Ctrl8Xl(Ctrl8Xl::from_bits_retain(value)),
+            RegisterAddress::CTRL9_XL => Self::Ctrl9Xl(Ctrl9Xl::from_bits_retain(value)),
+            RegisterAddress::CTRL10_C => Self::Ctrl10C(Ctrl10C::from_bits_retain(value)),
+            RegisterAddress::STATUS_REG => Self::StatusReg(StatusReg::from_bits_retain(value)),
+            RegisterAddress::EMB_FUNC_STATUS_MAINPAGE => {
+                Self::EmbFuncStatusMainpage(EmbFuncStatusMainpage::from_bits_retain(value))
+            }
+            RegisterAddress::FIFO_STATUS2 => {
+                Self::FifoStatus2(FifoStatus2::from_bits_retain(value))
+            }
+            RegisterAddress::TAP_CFG0 => Self::TapCfg0(TapCfg0::from_bits_retain(value)),
+            RegisterAddress::TAP_CFG1 => Self::TapCfg1(TapCfg1::from_bits_retain(value)),
+            RegisterAddress::TAP_CFG2 => Self::TapCfg2(TapCfg2::from_bits_retain(value)),
+            RegisterAddress::TAP_THS_6D => Self::TapThs6d(TapThs6d::from_bits_retain(value)),
+            RegisterAddress::INT_DUR2 => Self::IntDur2(IntDur2::from_bits_retain(value)),
+            RegisterAddress::WAKE_UP_THS => Self::WakeUpThs(WakeUpThs::from_bits_retain(value)),
+            RegisterAddress::MD1_CFG => Self::Md1Cfg(Md1Cfg::from_bits_retain(value)),
+            RegisterAddress::MD2_CFG => Self::Md2Cfg(Md2Cfg::from_bits_retain(value)),
+            _ => Self::Raw(value),
+        }
+    }
+
+    /// 読んだままの値
+    pub fn bits(&self) -> u8 {
+        match self {
+            Self::FifoCtrl2(reg) => reg.bits(),
+            Self::FifoCtrl3(reg) => reg.bits(),
+            Self::FifoCtrl4(reg) => reg.bits(),
+            Self::CounterBdrReg1(reg) => reg.bits(),
+            Self::Int1Ctrl(reg) => reg.bits(),
+            Self::Int2Ctrl(reg) => reg.bits(),
+            Self::Ctrl1Xl(reg) => reg.bits(),
+            Self::Ctrl2G(reg) => reg.bits(),
+            Self::Ctrl3C(reg) => reg.bits(),
+            Self::Ctrl4C(reg) => reg.bits(),
+            Self::Ctrl5C(reg) => reg.bits(),
+            Self::Ctrl6C(reg) => reg.bits(),
+            Self::Ctrl7G(reg) => reg.bits(),
+            Self::Ctrl8Xl(reg) => reg.bits(),
+            Self::Ctrl9Xl(reg) => reg.bits(),
+            Self::Ctrl10C(reg) => reg.bits(),
+            Self::StatusReg(reg) => reg.bits(),
+            Self::EmbFuncStatusMainpage(reg) => reg.bits(),
+            Self::FifoStatus2(reg) => reg.bits(),
+            Self::TapCfg0(reg) => reg.bits(),
+            Self::TapCfg1(reg) => reg.bits(),
+            Self::TapCfg2(reg) => reg.bits(),
+            Self::TapThs6d(reg) => reg.bits(),
+            Self::IntDur2(reg) => reg.bits(),
+            Self::WakeUpThs(reg) => reg.bits(),
+            Self::Md1Cfg(reg) => reg.bits(),
+            Self::Md2Cfg(reg) => reg.bits(),
+            Self::Raw(value) => *value,
+        }
+    }
+}
+
+/// 1つのレジスタを読んだ結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct RegisterSnapshot {
+    /// データシートのレジスタ名
+    pub name: &'static str,
+    pub address: u8,
+    /// 読んだままの値
+    pub raw: u8,
+    pub value: RegisterValue,
+}
+
+impl RegisterSnapshot {
+    pub fn new(name: &'static str, address: RegisterAddress, raw: u8) -> Self {
+        Self {
+            name,
+            address: address.bits(),
+            raw,
+            value: RegisterValue::decode(address, raw),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+//! ドライバのエラー
+
+use core::fmt;
+
+/// ドライバのエラー
+///
+/// `E` はバスのエラー
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Lsm6dsrxError<E> {
+    /// バスのエラー
+    Bus(E),
+    /// `WHO_AM_I` が期待する値と一致しない (読み出した値)
+    WhoAmI(u8),
+    /// リセットが時間内に完了しない
+    ResetTimeout,
+    /// セルフテストの結果が範囲外
+    SelfTestFailed,
+    /// FIFO が溢れてデータが失われた
+    FifoOverrun,
+    /// 設定値が範囲外
+    InvalidConfig,
+    /// 新しいデータが時間内に出力されない
+    DataReadyTimeout,
+    /// 書き込んだ値と読み返した値が一致しない
+    VerifyFailed {
+        address: u8,
+        expected: u8,
+        actual: u8,
+    },
+}
+
+impl<E: fmt::Debug> fmt::Display for Lsm6dsrxError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lsm6dsrxError::Bus(error) => write!(f, "Bus error: {error:?}"),
+            Lsm6dsrxError::WhoAmI(who_am_i) => {
+                write!(f, "Incorrect device: WHO_AM_I = {who_am_i:#04x}")
+            }
+            Lsm6dsrxError::ResetTimeout => write!(f, "Reset timed out"),
+            Lsm6dsrxError::SelfTestFailed => write!(f, "Self-test failed"),
+            Lsm6dsrxError::FifoOverrun => write!(f, "FIFO overrun"),
+            Lsm6dsrxError::InvalidConfig => write!(f, "Invalid config"),
+            Lsm6dsrxError::DataReadyTimeout => write!(f, "Data-ready timed out"),
+            Lsm6dsrxError::VerifyFailed {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Register {address:#04x} verification failed: wrote {expected:#04x}, read {actual:#04x}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug> std::error::Error for Lsm6dsrxError<E> {}
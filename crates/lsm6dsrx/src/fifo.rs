@@ -0,0 +1,476 @@
+//! FIFO の設定と読み出したデータ
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use schema::{Acceleration, AngularRate};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    to_celsius, AccelFullScale, Compensation, FifoCtrl3, FifoCtrl4, FifoStatus2, GyroFullScale,
+    Lsm6dsrxConfig, Lsm6dsrxError, RawAxes,
+};
+
+/// FIFO の watermark の最大値 [word]
+pub const FIFO_WATERMARK_MAX: u16 = 0x1FF;
+
+/// FIFO の動作モード (`FIFO_MODE[2:0]`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FifoMode {
+    /// Bypass mode: FIFO disabled
+    #[default]
+    Bypass,
+    /// FIFO mode: stops collecting data when FIFO is full
+    Fifo,
+    /// Continuous-to-FIFO mode: trigger を受けるまで continuous, その後は FIFO
+    ContinuousToFifo,
+    /// Bypass-to-continuous mode: trigger を受けるまで bypass, その後は continuous
+    BypassToContinuous,
+    /// Continuous mode: if the FIFO is full, the new sample overwrites the older one
+    Continuous,
+    /// Bypass-to-FIFO mode: trigger を受けるまで bypass, その後は FIFO
+    BypassToFifo,
+}
+
+impl FifoMode {
+    pub(super) const MASK: FifoCtrl4 = FifoCtrl4::FIFO_MODE2
+        .union(FifoCtrl4::FIFO_MODE1)
+        .union(FifoCtrl4::FIFO_MODE0);
+
+    /// `FIFO_CTRL4` の `FIFO_MODE[2:0]` に設定する値
+    pub(super) fn bits(self) -> FifoCtrl4 {
+        let mode: u8 = match self {
+            FifoMode::Bypass => 0b000,
+            FifoMode::Fifo => 0b001,
+            FifoMode::ContinuousToFifo => 0b011,
+            FifoMode::BypassToContinuous => 0b100,
+            FifoMode::Continuous => 0b110,
+            FifoMode::BypassToFifo => 0b111,
+        };
+        FifoCtrl4::from_bits_retain(mode)
+    }
+}
+
+/// 加速度計のデータを FIFO に入れるレート (`BDR_XL[3:0]`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum AccelBatchRate {
+    /// Not batched in FIFO
+    #[default]
+    NotBatched,
+    /// 1.6Hz
+    Hz1_6,
+    /// 12.5Hz
+    Hz12_5,
+    /// 26Hz
+    Hz26,
+    /// 52Hz
+    Hz52,
+    /// 104Hz
+    Hz104,
+    /// 208Hz
+    Hz208,
+    /// 417Hz
+    Hz417,
+    /// 833Hz
+    Hz833,
+    /// 1.667kHz
+    Hz1667,
+    /// 3.333kHz
+    Hz3333,
+    /// 6.667kHz
+    Hz6667,
+}
+
+impl AccelBatchRate {
+    pub(super) const MASK: FifoCtrl3 = FifoCtrl3::BDR_XL_3
+        .union(FifoCtrl3::BDR_XL_2)
+        .union(FifoCtrl3::BDR_XL_1)
+        .union(FifoCtrl3::BDR_XL_0);
+
+    /// `FIFO_CTRL3` の `BDR_XL[3:0]` に設定する値
+    pub(super) fn bits(self) -> FifoCtrl3 {
+        let bdr: u8 = match self {
+            AccelBatchRate::NotBatched => 0b0000,
+            AccelBatchRate::Hz1_6 => 0b1011,
+            AccelBatchRate::Hz12_5 => 0b0001,
+            AccelBatchRate::Hz26 => 0b0010,
+            AccelBatchRate::Hz52 => 0b0011,
+            AccelBatchRate::Hz104 => 0b0100,
+            AccelBatchRate::Hz208 => 0b0101,
+            AccelBatchRate::Hz417 => 0b0110,
+            AccelBatchRate::Hz833 => 0b0111,
+            AccelBatchRate::Hz1667 => 0b1000,
+            AccelBatchRate::Hz3333 => 0b1001,
+            AccelBatchRate::Hz6667 => 0b1010,
+        };
+        FifoCtrl3::from_bits_retain(bdr)
+    }
+}
+
+/// 角速度計のデータを FIFO に入れるレート (`BDR_GY[3:0]`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GyroBatchRate {
+    /// Not batched in FIFO
+    #[default]
+    NotBatched,
+    /// 6.5Hz
+    Hz6_5,
+    /// 12.5Hz
+    Hz12_5,
+    /// 26Hz
+    Hz26,
+    /// 52Hz
+    Hz52,
+    /// 104Hz
+    Hz104,
+    /// 208Hz
+    Hz208,
+    /// 417Hz
+    Hz417,
+    /// 833Hz
+    Hz833,
+    /// 1.667kHz
+    Hz1667,
+    /// 3.333kHz
+    Hz3333,
+    /// 6.667kHz
+    Hz6667,
+}
+
+impl GyroBatchRate {
+    pub(super) const MASK: FifoCtrl3 = FifoCtrl3::BDR_GY_3
+        .union(FifoCtrl3::BDR_GY_2)
+        .union(FifoCtrl3::BDR_GY_1)
+        .union(FifoCtrl3::BDR_GY_0);
+
+    /// `FIFO_CTRL3` の `BDR_GY[3:0]` に設定する値
+    pub(super) fn bits(self) -> FifoCtrl3 {
+        let bdr: u8 = match self {
+            GyroBatchRate::NotBatched => 0b0000,
+            GyroBatchRate::Hz6_5 => 0b1011,
+            GyroBatchRate::Hz12_5 => 0b0001,
+            GyroBatchRate::Hz26 => 0b0010,
+            GyroBatchRate::Hz52 => 0b0011,
+            GyroBatchRate::Hz104 => 0b0100,
+            GyroBatchRate::Hz208 => 0b0101,
+            GyroBatchRate::Hz417 => 0b0110,
+            GyroBatchRate::Hz833 => 0b0111,
+            GyroBatchRate::Hz1667 => 0b1000,
+            GyroBatchRate::Hz3333 => 0b1001,
+            GyroBatchRate::Hz6667 => 0b1010,
+        };
+        FifoCtrl3::from_bits_retain(bdr << 4)
+    }
+}
+
+/// 温度センサのデータを FIFO に入れるレート (`ODR_T_BATCH[1:0]`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TemperatureBatchRate {
+    /// Not batched in FIFO
+    #[default]
+    NotBatched,
+    /// 1.6Hz
+    Hz1_6,
+    /// 12.5Hz
+    Hz12_5,
+    /// 52Hz
+    Hz52,
+}
+
+impl TemperatureBatchRate {
+    pub(super) const MASK: FifoCtrl4 = FifoCtrl4::ODR_T_BATCH_1.union(FifoCtrl4::ODR_T_BATCH_0);
+
+    /// `FIFO_CTRL4` の `ODR_T_BATCH[1:0]` に設定する値
+    pub(super) fn bits(self) -> FifoCtrl4 {
+        match self {
+            TemperatureBatchRate::NotBatched => FifoCtrl4::empty(),
+            TemperatureBatchRate::Hz1_6 => FifoCtrl4::ODR_T_BATCH_0,
+            TemperatureBatchRate::Hz12_5 => FifoCtrl4::ODR_T_BATCH_1,
+            TemperatureBatchRate::Hz52 => FifoCtrl4::ODR_T_BATCH_1 | FifoCtrl4::ODR_T_BATCH_0,
+        }
+    }
+}
+
+/// タイムスタンプを FIFO に入れる間隔 (`DEC_TS_BATCH[1:0]`)
+///
+/// タイムスタンプを入れるにはタイムスタンプカウンタを有効にしておく必要がある
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TimestampBatch {
+    /// Timestamp not batched in FIFO
+    #[default]
+    NotBatched,
+    /// 加速度計、角速度計のうち速い方の BDR ごと
+    Every1,
+    /// 8 BDR ごと
+    Every8,
+    /// 32 BDR ごと
+    Every32,
+}
+
+impl TimestampBatch {
+    pub(super) const MASK: FifoCtrl4 = FifoCtrl4::DEC_TS_BATCH_1.union(FifoCtrl4::DEC_TS_BATCH_0);
+
+    /// `FIFO_CTRL4` の `DEC_TS_BATCH[1:0]` に設定する値
+    pub(super) fn bits(self) -> FifoCtrl4 {
+        match self {
+            TimestampBatch::NotBatched => FifoCtrl4::empty(),
+            TimestampBatch::Every1 => FifoCtrl4::DEC_TS_BATCH_0,
+            TimestampBatch::Every8 => FifoCtrl4::DEC_TS_BATCH_1,
+            TimestampBatch::Every32 => FifoCtrl4::DEC_TS_BATCH_1 | FifoCtrl4::DEC_TS_BATCH_0,
+        }
+    }
+}
+
+/// FIFO の設定
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(default)]
+pub struct FifoConfig {
+    /// FIFO watermark threshold [word] (最大 [`FIFO_WATERMARK_MAX`])
+    pub watermark: u16,
+    /// watermark に達したら FIFO を止める
+    pub stop_on_watermark: bool,
+    pub mode: FifoMode,
+    pub accel_batch_rate: AccelBatchRate,
+    pub gyro_batch_rate: GyroBatchRate,
+    pub temperature_batch_rate: TemperatureBatchRate,
+    pub timestamp_batch: TimestampBatch,
+}
+
+impl FifoConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn watermark(mut self, watermark: u16) -> Self {
+        self.watermark = watermark;
+        self
+    }
+
+    pub fn stop_on_watermark(mut self, stop_on_watermark: bool) -> Self {
+        self.stop_on_watermark = stop_on_watermark;
+        self
+    }
+
+    pub fn mode(mut self, mode: FifoMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn accel_batch_rate(mut self, accel_batch_rate: AccelBatchRate) -> Self {
+        self.accel_batch_rate = accel_batch_rate;
+        self
+    }
+
+    pub fn gyro_batch_rate(mut self, gyro_batch_rate: GyroBatchRate) -> Self {
+        self.gyro_batch_rate = gyro_batch_rate;
+        self
+    }
+
+    pub fn temperature_batch_rate(mut self, temperature_batch_rate: TemperatureBatchRate) -> Self {
+        self.temperature_batch_rate = temperature_batch_rate;
+        self
+    }
+
+    pub fn timestamp_batch(mut self, timestamp_batch: TimestampBatch) -> Self {
+        self.timestamp_batch = timestamp_batch;
+        self
+    }
+}
+
+/// FIFO_STATUS1, FIFO_STATUS2 の内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FifoStatus {
+    /// 未読のワード数
+    pub unread: u16,
+    /// watermark に達している
+    pub watermark: bool,
+    /// FIFO が溢れてデータが失われた
+    pub overrun: bool,
+    /// 次の書き込みで FIFO がいっぱいになる
+    pub full: bool,
+}
+
+impl FifoStatus {
+    pub(super) fn from_registers(status1: u8, status2: FifoStatus2) -> Self {
+        let mut unread = status1 as u16;
+        if status2.contains(FifoStatus2::DIFF_FIFO_8) {
+            unread |= 1 << 8;
+        }
+        if status2.contains(FifoStatus2::DIFF_FIFO_9) {
+            unread |= 1 << 9;
+        }
+        FifoStatus {
+            unread,
+            watermark: status2.contains(FifoStatus2::FIFO_WTM_IA),
+            overrun: status2.contains(FifoStatus2::FIFO_OVR_IA),
+            full: status2.contains(FifoStatus2::FIFO_FULL_IA),
+        }
+    }
+}
+
+/// FIFO から読み出したデータ
+#[derive(Debug, Clone, PartialEq)]
+pub enum FifoSample {
+    Acceleration(Acceleration),
+    AngularRate(AngularRate),
+    /// [℃]
+    Temperature(f64),
+    /// タイムスタンプカウンタの値
+    Timestamp(u32),
+}
+
+/// [`Lsm6sdrx::drain_fifo`](super::Lsm6sdrx::drain_fifo) で読み出したデータ
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FifoDrain {
+    pub samples: Vec<FifoSample>,
+    /// FIFO が溢れて、`samples` より前のデータが失われていた
+    pub overrun: bool,
+}
+
+#[cfg(feature = "alloc")]
+impl FifoDrain {
+    /// `drain_fifo_with` で読み出した `samples` と、その結果をまとめる
+    pub(super) fn new<E>(
+        samples: Vec<FifoSample>,
+        result: Result<usize, Lsm6dsrxError<E>>,
+    ) -> Result<Self, Lsm6dsrxError<E>> {
+        let overrun = match result {
+            Ok(_) => false,
+            Err(Lsm6dsrxError::FifoOverrun) => true,
+            Err(error) => return Err(error),
+        };
+        Ok(FifoDrain { samples, overrun })
+    }
+}
+
+/// FIFO_DATA_OUT_TAG (0x78) から FIFO_DATA_OUT_Z_H (0x7E) までの生の値
+#[repr(C, packed)]
+#[derive(Default, Debug, Clone, Copy)]
+pub(super) struct RawFifoWord {
+    pub tag: u8,
+    pub data: RawAxes,
+}
+
+impl RawFifoWord {
+    /// Gyroscope NC
+    const TAG_GYRO_NC: u8 = 0x01;
+    /// Accelerometer NC
+    const TAG_ACCEL_NC: u8 = 0x02;
+    /// Temperature
+    const TAG_TEMPERATURE: u8 = 0x03;
+    /// Timestamp
+    const TAG_TIMESTAMP: u8 = 0x04;
+
+    /// `TAG_SENSOR[4:0]` を見てデータを変換する
+    ///
+    /// 加速度、角速度には `timestamp` を付ける。対応していないタグの場合は `None` を返す
+    fn decode(
+        self,
+        accel_full_scale: AccelFullScale,
+        gyro_full_scale: GyroFullScale,
+        timestamp: Option<u32>,
+    ) -> Option<FifoSample> {
+        let data = self.data;
+        match self.tag >> 3 {
+            Self::TAG_GYRO_NC => Some(FifoSample::AngularRate(
+                data.to_angular_rate(gyro_full_scale, timestamp),
+            )),
+            Self::TAG_ACCEL_NC => Some(FifoSample::Acceleration(
+                data.to_acceleration(accel_full_scale, timestamp),
+            )),
+            Self::TAG_TEMPERATURE => Some(FifoSample::Temperature(to_celsius(data.x))),
+            Self::TAG_TIMESTAMP => {
+                let low = data.x as u16 as u32;
+                let high = data.y as u16 as u32;
+                Some(FifoSample::Timestamp(high << 16 | low))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// FIFO の読み出し1回分
+///
+/// [`FifoDrainer::unread`] の数だけワードを読んで [`FifoDrainer::sample`] に渡し、最後に
+/// [`FifoDrainer::finish`] する
+pub(super) struct FifoDrainer {
+    status: FifoStatus,
+    decoder: FifoDecoder,
+}
+
+impl FifoDrainer {
+    /// 読み出しを始める前に読んだ `status` と、現在の `config` で変換する
+    pub(super) fn new(status: FifoStatus, config: &Lsm6dsrxConfig) -> Self {
+        FifoDrainer {
+            status,
+            decoder: FifoDecoder::new(config.accel_full_scale, config.gyro_full_scale),
+        }
+    }
+
+    /// 読み出すワード数
+    pub(super) fn unread(&self) -> u16 {
+        self.status.unread
+    }
+
+    /// 読んだワードを変換して補正する
+    ///
+    /// 対応していないタグの場合は `None` を返す
+    pub(super) fn sample(
+        &mut self,
+        word: RawFifoWord,
+        compensation: &mut Compensation,
+    ) -> Option<FifoSample> {
+        let Some(sample) = self.decoder.decode(word) else {
+            debug!("Skipped FIFO word, tag = {:#x}", { word.tag });
+            return None;
+        };
+        Some(compensation.fifo_sample(sample))
+    }
+
+    /// 読み出したワード数を返す
+    ///
+    /// FIFO が溢れていた場合は [`Lsm6dsrxError::FifoOverrun`] を返す
+    pub(super) fn finish<E>(self) -> Result<usize, Lsm6dsrxError<E>> {
+        if self.status.overrun {
+            warn!("FIFO overrun.");
+            return Err(Lsm6dsrxError::FifoOverrun);
+        }
+        Ok(self.status.unread as usize)
+    }
+}
+
+/// FIFO から読み出したワードを順番に変換する
+///
+/// 加速度、角速度には直前に読んだタイムスタンプを付ける
+struct FifoDecoder {
+    accel_full_scale: AccelFullScale,
+    gyro_full_scale: GyroFullScale,
+    timestamp: Option<u32>,
+}
+
+impl FifoDecoder {
+    fn new(accel_full_scale: AccelFullScale, gyro_full_scale: GyroFullScale) -> Self {
+        FifoDecoder {
+            accel_full_scale,
+            gyro_full_scale,
+            timestamp: None,
+        }
+    }
+
+    /// 対応していないタグの場合は `None` を返す
+    fn decode(&mut self, word: RawFifoWord) -> Option<FifoSample> {
+        let sample = word.decode(self.accel_full_scale, self.gyro_full_scale, self.timestamp)?;
+        if let FifoSample::Timestamp(timestamp) = sample {
+            self.timestamp = Some(timestamp);
+        }
+        Some(sample)
+    }
+}
@@ -0,0 +1,23 @@
+//! `log` と `defmt` のどちらか (あるいは両方) にログを出すためのマクロ
+//!
+//! どちらの feature も無効な場合は何もしない
+
+#![allow(unused_macros)]
+
+macro_rules! debug {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::debug!($($arg)*);
+        #[cfg(feature = "defmt")]
+        defmt::debug!($($arg)*);
+    }};
+}
+
+macro_rules! warn {
+    ($($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::warn!($($arg)*);
+        #[cfg(feature = "defmt")]
+        defmt::warn!($($arg)*);
+    }};
+}
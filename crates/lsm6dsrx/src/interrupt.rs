@@ -0,0 +1,425 @@
+//! 割り込みピンの設定と割り込み要因
+
+use schema::TapEvent;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AllIntSrc, Ctrl3C, D6dSrc, EmbFuncStatusMainpage, FifoStatus2, Int1Ctrl, Int2Ctrl, Md1Cfg,
+    Md2Cfg, StatusReg, TapSrc, WakeUpSrc,
+};
+
+/// 割り込みピンがアクティブになるレベル (`H_LACTIVE`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptPolarity {
+    /// Interrupt output pins active high
+    #[default]
+    ActiveHigh,
+    /// Interrupt output pins active low
+    ActiveLow,
+}
+
+impl InterruptPolarity {
+    pub(super) const MASK: Ctrl3C = Ctrl3C::H_LACTIVE;
+
+    /// `CTRL3_C` の `H_LACTIVE` に設定する値
+    pub(super) fn bits(self) -> Ctrl3C {
+        match self {
+            InterruptPolarity::ActiveHigh => Ctrl3C::empty(),
+            InterruptPolarity::ActiveLow => Ctrl3C::H_LACTIVE,
+        }
+    }
+}
+
+/// 割り込みピンの出力 (`PP_OD`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptPinMode {
+    /// Push-pull mode
+    #[default]
+    PushPull,
+    /// Open-drain mode
+    OpenDrain,
+}
+
+impl InterruptPinMode {
+    pub(super) const MASK: Ctrl3C = Ctrl3C::PP_OD;
+
+    /// `CTRL3_C` の `PP_OD` に設定する値
+    pub(super) fn bits(self) -> Ctrl3C {
+        match self {
+            InterruptPinMode::PushPull => Ctrl3C::empty(),
+            InterruptPinMode::OpenDrain => Ctrl3C::PP_OD,
+        }
+    }
+}
+
+/// 割り込み信号の出し方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum InterruptSignal {
+    /// 一定時間だけアクティブにする
+    Pulsed,
+    /// 要因のレジスタを読むまでアクティブのままにする
+    Latched,
+}
+
+/// 割り込みピンに出すイベント
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(default)]
+pub struct InterruptRoute {
+    /// 加速度の data-ready
+    pub accel_data_ready: bool,
+    /// 角速度の data-ready
+    pub gyro_data_ready: bool,
+    /// 温度の data-ready (INT2 にだけ出せる)
+    pub temperature_data_ready: bool,
+    /// FIFO が watermark に達した
+    pub fifo_watermark: bool,
+    /// FIFO が溢れた
+    pub fifo_overrun: bool,
+    /// FIFO がいっぱいになる
+    pub fifo_full: bool,
+    pub single_tap: bool,
+    pub double_tap: bool,
+    pub wake_up: bool,
+    pub free_fall: bool,
+    /// 6D (向きの変化)
+    pub orientation: bool,
+    /// activity/inactivity の変化
+    pub sleep_change: bool,
+    /// 組み込み機能 (歩数計、FSM、MLC など)
+    pub embedded_function: bool,
+}
+
+impl InterruptRoute {
+    /// `INT1_CTRL` のうち [`InterruptRoute`] で設定するビット
+    pub(super) const INT1_CTRL_MASK: Int1Ctrl = Int1Ctrl::INT1_FIFO_FULL
+        .union(Int1Ctrl::INT1_FIFO_OVR)
+        .union(Int1Ctrl::INT1_FIFO_TH)
+        .union(Int1Ctrl::INT1_DRDY_G)
+        .union(Int1Ctrl::INT1_DRDY_XL);
+
+    /// `INT2_CTRL` のうち [`InterruptRoute`] で設定するビット
+    pub(super) const INT2_CTRL_MASK: Int2Ctrl = Int2Ctrl::INT2_FIFO_FULL
+        .union(Int2Ctrl::INT2_FIFO_OVR)
+        .union(Int2Ctrl::INT2_FIFO_TH)
+        .union(Int2Ctrl::INT2_DRDY_TEMP)
+        .union(Int2Ctrl::INT2_DRDY_G)
+        .union(Int2Ctrl::INT2_DRDY_XL);
+
+    /// `MD1_CFG` のうち [`InterruptRoute`] で設定するビット
+    pub(super) const MD1_CFG_MASK: Md1Cfg = Md1Cfg::INT1_SLEEP_CHANGE
+        .union(Md1Cfg::INT1_SINGLE_TAP)
+        .union(Md1Cfg::INT1_WU)
+        .union(Md1Cfg::INT1_FF)
+        .union(Md1Cfg::INT1_DOUBLE_TAP)
+        .union(Md1Cfg::INT1_6D)
+        .union(Md1Cfg::INT1_EMB_FUNC);
+
+    /// `MD2_CFG` のうち [`InterruptRoute`] で設定するビット
+    pub(super) const MD2_CFG_MASK: Md2Cfg = Md2Cfg::INT2_SLEEP_CHANGE
+        .union(Md2Cfg::INT2_SINGLE_TAP)
+        .union(Md2Cfg::INT2_WU)
+        .union(Md2Cfg::INT2_FF)
+        .union(Md2Cfg::INT2_DOUBLE_TAP)
+        .union(Md2Cfg::INT2_6D)
+        .union(Md2Cfg::INT2_EMB_FUNC);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn accel_data_ready(mut self, accel_data_ready: bool) -> Self {
+        self.accel_data_ready = accel_data_ready;
+        self
+    }
+
+    pub fn gyro_data_ready(mut self, gyro_data_ready: bool) -> Self {
+        self.gyro_data_ready = gyro_data_ready;
+        self
+    }
+
+    pub fn temperature_data_ready(mut self, temperature_data_ready: bool) -> Self {
+        self.temperature_data_ready = temperature_data_ready;
+        self
+    }
+
+    pub fn fifo_watermark(mut self, fifo_watermark: bool) -> Self {
+        self.fifo_watermark = fifo_watermark;
+        self
+    }
+
+    pub fn fifo_overrun(mut self, fifo_overrun: bool) -> Self {
+        self.fifo_overrun = fifo_overrun;
+        self
+    }
+
+    pub fn fifo_full(mut self, fifo_full: bool) -> Self {
+        self.fifo_full = fifo_full;
+        self
+    }
+
+    pub fn single_tap(mut self, single_tap: bool) -> Self {
+        self.single_tap = single_tap;
+        self
+    }
+
+    pub fn double_tap(mut self, double_tap: bool) -> Self {
+        self.double_tap = double_tap;
+        self
+    }
+
+    pub fn wake_up(mut self, wake_up: bool) -> Self {
+        self.wake_up = wake_up;
+        self
+    }
+
+    pub fn free_fall(mut self, free_fall: bool) -> Self {
+        self.free_fall = free_fall;
+        self
+    }
+
+    pub fn orientation(mut self, orientation: bool) -> Self {
+        self.orientation = orientation;
+        self
+    }
+
+    pub fn sleep_change(mut self, sleep_change: bool) -> Self {
+        self.sleep_change = sleep_change;
+        self
+    }
+
+    pub fn embedded_function(mut self, embedded_function: bool) -> Self {
+        self.embedded_function = embedded_function;
+        self
+    }
+
+    /// `INTERRUPTS_ENABLE` が必要な基本の割り込み (タップ、wake-up、free-fall、6D、activity) を使う
+    pub(super) fn uses_basic_interrupts(&self) -> bool {
+        self.single_tap
+            || self.double_tap
+            || self.wake_up
+            || self.free_fall
+            || self.orientation
+            || self.sleep_change
+    }
+
+    /// `INT1_CTRL` に設定する値
+    pub(super) fn int1_ctrl(&self) -> Int1Ctrl {
+        let mut reg = Int1Ctrl::empty();
+        reg.set(Int1Ctrl::INT1_FIFO_FULL, self.fifo_full);
+        reg.set(Int1Ctrl::INT1_FIFO_OVR, self.fifo_overrun);
+        reg.set(Int1Ctrl::INT1_FIFO_TH, self.fifo_watermark);
+        reg.set(Int1Ctrl::INT1_DRDY_G, self.gyro_data_ready);
+        reg.set(Int1Ctrl::INT1_DRDY_XL, self.accel_data_ready);
+        reg
+    }
+
+    /// `INT2_CTRL` に設定する値
+    pub(super) fn int2_ctrl(&self) -> Int2Ctrl {
+        let mut reg = Int2Ctrl::empty();
+        reg.set(Int2Ctrl::INT2_FIFO_FULL, self.fifo_full);
+        reg.set(Int2Ctrl::INT2_FIFO_OVR, self.fifo_overrun);
+        reg.set(Int2Ctrl::INT2_FIFO_TH, self.fifo_watermark);
+        reg.set(Int2Ctrl::INT2_DRDY_TEMP, self.temperature_data_ready);
+        reg.set(Int2Ctrl::INT2_DRDY_G, self.gyro_data_ready);
+        reg.set(Int2Ctrl::INT2_DRDY_XL, self.accel_data_ready);
+        reg
+    }
+
+    /// `MD1_CFG` に設定する値
+    pub(super) fn md1_cfg(&self) -> Md1Cfg {
+        let mut reg = Md1Cfg::empty();
+        reg.set(Md1Cfg::INT1_SLEEP_CHANGE, self.sleep_change);
+        reg.set(Md1Cfg::INT1_SINGLE_TAP, self.single_tap);
+        reg.set(Md1Cfg::INT1_WU, self.wake_up);
+        reg.set(Md1Cfg::INT1_FF, self.free_fall);
+        reg.set(Md1Cfg::INT1_DOUBLE_TAP, self.double_tap);
+        reg.set(Md1Cfg::INT1_6D, self.orientation);
+        reg.set(Md1Cfg::INT1_EMB_FUNC, self.embedded_function);
+        reg
+    }
+
+    /// `MD2_CFG` に設定する値
+    pub(super) fn md2_cfg(&self) -> Md2Cfg {
+        let mut reg = Md2Cfg::empty();
+        reg.set(Md2Cfg::INT2_SLEEP_CHANGE, self.sleep_change);
+        reg.set(Md2Cfg::INT2_SINGLE_TAP, self.single_tap);
+        reg.set(Md2Cfg::INT2_WU, self.wake_up);
+        reg.set(Md2Cfg::INT2_FF, self.free_fall);
+        reg.set(Md2Cfg::INT2_DOUBLE_TAP, self.double_tap);
+        reg.set(Md2Cfg::INT2_6D, self.orientation);
+        reg.set(Md2Cfg::INT2_EMB_FUNC, self.embedded_function);
+        reg
+    }
+}
+
+/// 割り込みピンの設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(default)]
+pub struct InterruptConfig {
+    /// INT1 に出すイベント
+    pub int1: InterruptRoute,
+    /// INT2 に出すイベント
+    pub int2: InterruptRoute,
+    pub polarity: InterruptPolarity,
+    pub pin_mode: InterruptPinMode,
+    /// タップ、wake-up、free-fall、6D などのイベントの出し方 (`LIR`)
+    pub event_signal: InterruptSignal,
+    /// data-ready の出し方 (`dataready_pulsed`)
+    pub data_ready_signal: InterruptSignal,
+}
+
+impl Default for InterruptConfig {
+    /// どのイベントも出さない、リセット直後の設定
+    fn default() -> Self {
+        Self {
+            int1: InterruptRoute::default(),
+            int2: InterruptRoute::default(),
+            polarity: InterruptPolarity::default(),
+            pin_mode: InterruptPinMode::default(),
+            event_signal: InterruptSignal::Pulsed,
+            data_ready_signal: InterruptSignal::Latched,
+        }
+    }
+}
+
+impl InterruptConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn int1(mut self, int1: InterruptRoute) -> Self {
+        self.int1 = int1;
+        self
+    }
+
+    pub fn int2(mut self, int2: InterruptRoute) -> Self {
+        self.int2 = int2;
+        self
+    }
+
+    pub fn polarity(mut self, polarity: InterruptPolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    pub fn pin_mode(mut self, pin_mode: InterruptPinMode) -> Self {
+        self.pin_mode = pin_mode;
+        self
+    }
+
+    pub fn event_signal(mut self, event_signal: InterruptSignal) -> Self {
+        self.event_signal = event_signal;
+        self
+    }
+
+    pub fn data_ready_signal(mut self, data_ready_signal: InterruptSignal) -> Self {
+        self.data_ready_signal = data_ready_signal;
+        self
+    }
+
+    /// レジスタに書き込める組み合わせか
+    pub(super) fn is_valid(&self) -> bool {
+        // 温度の data-ready は INT1 に出せない
+        if self.int1.temperature_data_ready {
+            return false;
+        }
+        // `H_LACTIVE` が 1 のときは `PP_OD` を 0 にする必要がある
+        !(self.polarity == InterruptPolarity::ActiveLow
+            && self.pin_mode == InterruptPinMode::OpenDrain)
+    }
+
+    /// `INTERRUPTS_ENABLE` が必要か
+    pub(super) fn uses_basic_interrupts(&self) -> bool {
+        self.int1.uses_basic_interrupts() || self.int2.uses_basic_interrupts()
+    }
+}
+
+/// 発生している割り込みの要因
+///
+/// ALL_INT_SRC から D6D_SRC, STATUS_REG, EMB_FUNC_STATUS_MAINPAGE から FIFO_STATUS2 までの内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct InterruptSources {
+    pub accel_data_ready: bool,
+    pub gyro_data_ready: bool,
+    pub temperature_data_ready: bool,
+    pub fifo_watermark: bool,
+    pub fifo_overrun: bool,
+    pub fifo_full: bool,
+    pub single_tap: bool,
+    pub double_tap: bool,
+    pub wake_up: bool,
+    pub free_fall: bool,
+    /// 6D (向きの変化)
+    pub orientation: bool,
+    /// activity/inactivity の変化
+    pub sleep_change: bool,
+    /// 組み込み機能 (歩数計、FSM、MLC など)
+    pub embedded_function: bool,
+    /// wake-up、free-fall の詳細
+    pub wake_up_source: WakeUpSrc,
+    /// タップの軸と向き
+    pub tap_source: TapSrc,
+    /// 6D の向き
+    pub orientation_source: D6dSrc,
+    /// 組み込み機能の詳細
+    pub embedded_function_source: EmbFuncStatusMainpage,
+}
+
+impl InterruptSources {
+    /// `sources` は ALL_INT_SRC (0x1A) から STATUS_REG (0x1E) まで、
+    /// `mainpage` は EMB_FUNC_STATUS_MAINPAGE (0x35) から FIFO_STATUS2 (0x3B) までの値
+    pub(super) fn from_registers(sources: [u8; 5], mainpage: [u8; 7]) -> Self {
+        let [all, wake_up, tap, d6d, status] = sources;
+        let all = AllIntSrc::from_bits_retain(all);
+        let status = StatusReg::from_bits_retain(status);
+        let [emb_func, fsm_a, fsm_b, mlc, _, _, fifo] = mainpage;
+        let emb_func = EmbFuncStatusMainpage::from_bits_retain(emb_func);
+        let fifo = FifoStatus2::from_bits_retain(fifo);
+        InterruptSources {
+            accel_data_ready: status.contains(StatusReg::XLDA),
+            gyro_data_ready: status.contains(StatusReg::GDA),
+            temperature_data_ready: status.contains(StatusReg::TDA),
+            fifo_watermark: fifo.contains(FifoStatus2::FIFO_WTM_IA),
+            fifo_overrun: fifo.contains(FifoStatus2::FIFO_OVR_IA),
+            fifo_full: fifo.contains(FifoStatus2::FIFO_FULL_IA),
+            single_tap: all.contains(AllIntSrc::SINGLE_TAP),
+            double_tap: all.contains(AllIntSrc::DOUBLE_TAP),
+            wake_up: all.contains(AllIntSrc::WU_IA),
+            free_fall: all.contains(AllIntSrc::FF_IA),
+            orientation: all.contains(AllIntSrc::D6D_IA),
+            sleep_change: all.contains(AllIntSrc::SLEEP_CHANGE_IA),
+            embedded_function: !emb_func.is_empty() || fsm_a != 0 || fsm_b != 0 || mlc != 0,
+            wake_up_source: WakeUpSrc::from_bits_retain(wake_up),
+            tap_source: TapSrc::from_bits_retain(tap),
+            orientation_source: D6dSrc::from_bits_retain(d6d),
+            embedded_function_source: emb_func,
+        }
+    }
+
+    /// 検出したタップ
+    pub fn tap(&self) -> Option<TapEvent> {
+        self.tap_source.event(None)
+    }
+
+    /// いずれかのイベントが発生している
+    pub fn any(&self) -> bool {
+        self.accel_data_ready
+            || self.gyro_data_ready
+            || self.temperature_data_ready
+            || self.fifo_watermark
+            || self.fifo_overrun
+            || self.fifo_full
+            || self.single_tap
+            || self.double_tap
+            || self.wake_up
+            || self.free_fall
+            || self.orientation
+            || self.sleep_change
+            || self.embedded_function
+    }
+}
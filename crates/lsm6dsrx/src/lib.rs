@@ -0,0 +1,1567 @@
+//! https://www.st.com/resource/en/datasheet/lsm6dsrx.pdf
+//! 真似してる -> https://github.com/ypc2e55orj/esp32s3_playground/blob/main/imu/main/imu.cc
+
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use core::ops::{Deref, DerefMut};
+
+use bitflags::bitflags;
+use schema::{Acceleration, AngularRate, Calibration, MotionSample, TapEvent, Temperature};
+use serde::Serialize;
+
+#[cfg(feature = "async")]
+pub use self::asynch::*;
+pub use self::{
+    bias::*, bus::*, calibration::*, config::*, dump::*, error::*, fifo::*, interrupt::*,
+    register::*, self_test::*, tap::*,
+};
+use self::{compensation::Compensation, sequence::RegisterWrite};
+
+#[macro_use]
+mod fmt;
+
+#[cfg(feature = "async")]
+mod asynch;
+mod bias;
+mod bus;
+mod calibration;
+mod compensation;
+mod config;
+mod dump;
+mod error;
+mod fifo;
+mod interrupt;
+mod register;
+mod self_test;
+mod sequence;
+mod tap;
+
+/// 期待する `WHO_AM_I`
+const DEFAULT_WHO_AM_I: u8 = 0x6B;
+
+/// `TIMESTAMP2` に書き込むとタイムスタンプカウンタがリセットされる値
+const TIMESTAMP_RESET: u8 = 0xAA;
+
+/// `SW_RESET`, `BOOT` が 0 に戻ったか確認する間隔 [µs]
+const RESET_POLL_INTERVAL_US: u32 = 50;
+
+/// `SW_RESET` が 0 に戻るまで待つ最大の時間 [µs]
+const SW_RESET_TIMEOUT_US: u32 = 10_000;
+
+/// `BOOT` を立ててから待つ時間 [ms]
+const BOOT_TIME_MS: u32 = 10;
+
+/// `BOOT_TIME_MS` 待ったあと、`BOOT` が 0 に戻るまで待つ最大の時間 [µs]
+const BOOT_TIMEOUT_US: u32 = 50_000;
+
+/// `STATUS_REG` で新しいデータが出力されたか確認する間隔 [µs]
+const DATA_READY_POLL_INTERVAL_US: u32 = 100;
+
+/// 重力加速度 [mg]
+const STANDARD_GRAVITY: f64 = 1000.0;
+
+/// オフセットのキャリブレーションで、平均をとり始める前に捨てるサンプル数
+const CALIBRATION_DISCARD_SAMPLES: u32 = 2;
+
+/// [LSB/℃]
+const TEMPERATURE_SENSITIVITY: f64 = 256.0;
+
+/// 出力が 0 のときの温度 [℃]
+const TEMPERATURE_OFFSET: f64 = 25.0;
+
+bitflags! {
+    /// 8. Register mapping
+    /// Table 20. Registers addresses map
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct RegisterAddress: u8 {
+        const FUNC_CFG_ACCESS = 0x00;
+        const PIN_CTRL = 0x02;
+        const S4S_TPH_L = 0x04;
+        const S4S_TPH_H = 0x05;
+        const S4S_RR = 0x06;
+        const FIFO_CTRL1 = 0x07;
+        const FIFO_CTRL2 = 0x08;
+        const FIFO_CTRL3 = 0x09;
+        const FIFO_CTRL4 = 0x0A;
+        const COUNTER_BDR_REG1 = 0x0B;
+        const COUNTER_BDR_REG2 = 0x0C;
+        const INT1_CTRL = 0x0D;
+        const INT2_CTRL = 0x0E;
+        const WHO_AM_I = 0x0F;
+        const CTRL1_XL = 0x10;
+        const CTRL2_G = 0x11;
+        const CTRL3_C = 0x12;
+        const CTRL4_C = 0x13;
+        const CTRL5_C = 0x14;
+        const CTRL6_C = 0x15;
+        const CTRL7_G = 0x16;
+        const CTRL8_XL = 0x17;
+        const CTRL9_XL = 0x18;
+        const CTRL10_C = 0x19;
+        const ALL_INT_SRC = 0x1A;
+        const WAKE_UP_SRC = 0x1B;
+        const TAP_SRC = 0x1C;
+        const D6D_SRC = 0x1D;
+        const STATUS_REG = 0x1E;
+        const OUT_TEMP_L = 0x20;
+        const OUT_TEMP_H = 0x21;
+        const OUTX_L_G = 0x22;
+        const OUTX_H_G = 0x23;
+        const OUTY_L_G = 0x24;
+        const OUTY_H_G = 0x25;
+        const OUTZ_L_G = 0x26;
+        const OUTZ_H_G = 0x27;
+        const OUTX_L_A = 0x28;
+        const OUTX_H_A = 0x29;
+        const OUTY_L_A = 0x2A;
+        const OUTY_H_A = 0x2B;
+        const OUTZ_L_A = 0x2C;
+        const OUTZ_H_A = 0x2D;
+        const EMB_FUNC_STATUS_MAINPAGE = 0x35;
+        const FSM_STATUS_A_MAINPAGE = 0x36;
+        const FSM_STATUS_B_MAINPAGE = 0x37;
+        const MLC_STATUS_MAINPAGE = 0x38;
+        const STATUS_MASTER_MAINPAGE = 0x39;
+        const FIFO_STATUS1 = 0x3A;
+        const FIFO_STATUS2 = 0x3B;
+        const TIMESTAMP0 = 0x40;
+        const TIMESTAMP1 = 0x41;
+        const TIMESTAMP2 = 0x42;
+        const TIMESTAMP3 = 0x43;
+        const TAP_CFG0 = 0x56;
+        const TAP_CFG1 = 0x57;
+        const TAP_CFG2 = 0x58;
+        const TAP_THS_6D = 0x59;
+        const INT_DUR2 = 0x5A;
+        const WAKE_UP_THS = 0x5B;
+        const WAKE_UP_DUR = 0x5C;
+        const FREE_FALL = 0x5D;
+        const MD1_CFG = 0x5E;
+        const MD2_CFG = 0x5F;
+        const S4S_ST_CMD_CODE = 0x60;
+        const S4S_DT_REG = 0x61;
+        const I3C_BUS_AVB = 0x62;
+        const INTERNAL_FREQ_FINE = 0x63;
+        const INT_OIS = 0x6F;
+        const CTRL1_OIS = 0x70;
+        const CTRL2_OIS = 0x71;
+        const CTRL3_OIS = 0x72;
+        const X_OFS_USR = 0x73;
+        const Y_OFS_USR = 0x74;
+        const Z_OFS_USR = 0x75;
+        const FIFO_DATA_OUT_TAG = 0x78;
+        const FIFO_DATA_OUT_X_L = 0x79;
+        const FIFO_DATA_OUT_X_H = 0x7A;
+        const FIFO_DATA_OUT_Y_L = 0x7B;
+        const FIFO_DATA_OUT_Y_H = 0x7C;
+        const FIFO_DATA_OUT_Z_L = 0x7D;
+        const FIFO_DATA_OUT_Z_H = 0x7E;
+    }
+
+    /// FIFO_CTRL2 (0x08)
+    /// FIFO control register 2 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct FifoCtrl2: u8 {
+        /// Sensing chain FIFO stop values memorization at threshold level
+        const STOP_ON_WTM = 0b1000_0000;
+        const FIFO_COMPR_RT_EN = 0b0100_0000;
+        const ODRCHG_EN = 0b0001_0000;
+        const UNCOPTR_RATE_1 = 0b0000_0100;
+        const UNCOPTR_RATE_0 = 0b0000_0010;
+        /// FIFO watermark threshold の 9 ビット目
+        const WTM8 = 0b0000_0001;
+    }
+
+    /// FIFO_CTRL3 (0x09)
+    /// FIFO control register 3 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct FifoCtrl3: u8 {
+        const BDR_GY_3 = 0b1000_0000;
+        const BDR_GY_2 = 0b0100_0000;
+        const BDR_GY_1 = 0b0010_0000;
+        const BDR_GY_0 = 0b0001_0000;
+        const BDR_XL_3 = 0b0000_1000;
+        const BDR_XL_2 = 0b0000_0100;
+        const BDR_XL_1 = 0b0000_0010;
+        const BDR_XL_0 = 0b0000_0001;
+    }
+
+    /// FIFO_CTRL4 (0x0A)
+    /// FIFO control register 4 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct FifoCtrl4: u8 {
+        const DEC_TS_BATCH_1 = 0b1000_0000;
+        const DEC_TS_BATCH_0 = 0b0100_0000;
+        const ODR_T_BATCH_1 = 0b0010_0000;
+        const ODR_T_BATCH_0 = 0b0001_0000;
+        const FIFO_MODE2 = 0b0000_0100;
+        const FIFO_MODE1 = 0b0000_0010;
+        const FIFO_MODE0 = 0b0000_0001;
+    }
+
+    /// COUNTER_BDR_REG1 (0x0B)
+    /// Counter batch data rate register 1 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct CounterBdrReg1: u8 {
+        /// Enables pulsed data-ready mode. Default value: 0
+        /// (0: data-ready latched mode; 1: data-ready pulsed mode (pulse duration: 75 µs))
+        const DATAREADY_PULSED = 0b1000_0000;
+        /// Resets the internal counter of batch events for a single sensor.
+        /// This bit is automatically reset to zero if it was set to '1'.
+        const RST_COUNTER_BDR = 0b0100_0000;
+        /// Selects the trigger for the internal counter of batch events between XL and gyro.
+        /// (0: XL batch event; 1: gyro batch event)
+        const TRIG_COUNTER_BDR = 0b0010_0000;
+        const CNT_BDR_TH_10 = 0b0000_0100;
+        const CNT_BDR_TH_9 = 0b0000_0010;
+        const CNT_BDR_TH_8 = 0b0000_0001;
+    }
+
+    /// INT1_CTRL (0x0D)
+    /// INT1 pin control register (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Int1Ctrl: u8 {
+        /// Sends DEN_DRDY (DEN stamped on sensor data flag) to INT1 pin.
+        const DEN_DRDY_FLAG = 0b1000_0000;
+        /// Enables COUNTER_BDR_IA interrupt on INT1.
+        const INT1_CNT_BDR = 0b0100_0000;
+        /// Enables FIFO full flag interrupt on INT1 pin.
+        const INT1_FIFO_FULL = 0b0010_0000;
+        /// Enables FIFO overrun interrupt on INT1 pin.
+        const INT1_FIFO_OVR = 0b0001_0000;
+        /// Enables FIFO threshold interrupt on INT1 pin.
+        const INT1_FIFO_TH = 0b0000_1000;
+        /// Enables boot status on INT1 pin.
+        const INT1_BOOT = 0b0000_0100;
+        /// Enables gyroscope data-ready interrupt on INT1 pin.
+        const INT1_DRDY_G = 0b0000_0010;
+        /// Enables accelerometer data-ready interrupt on INT1 pin.
+        const INT1_DRDY_XL = 0b0000_0001;
+    }
+
+    /// INT2_CTRL (0x0E)
+    /// INT2 pin control register (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Int2Ctrl: u8 {
+        /// Enables COUNTER_BDR_IA interrupt on INT2 pin.
+        const INT2_CNT_BDR = 0b0100_0000;
+        /// Enables FIFO full flag interrupt on INT2 pin.
+        const INT2_FIFO_FULL = 0b0010_0000;
+        /// Enables FIFO overrun interrupt on INT2 pin.
+        const INT2_FIFO_OVR = 0b0001_0000;
+        /// Enables FIFO threshold interrupt on INT2 pin.
+        const INT2_FIFO_TH = 0b0000_1000;
+        /// Enables temperature sensor data-ready interrupt on INT2 pin.
+        const INT2_DRDY_TEMP = 0b0000_0100;
+        /// Enables gyroscope data-ready interrupt on INT2 pin.
+        const INT2_DRDY_G = 0b0000_0010;
+        /// Enables accelerometer data-ready interrupt on INT2 pin.
+        const INT2_DRDY_XL = 0b0000_0001;
+    }
+
+    /// CTRL1_XL (0x10)
+    /// Accelerometer control register 1 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl1Xl: u8 {
+        const ODR_XL3 = 0b1000_0000;
+        const ODR_XL2 = 0b0100_0000;
+        const ODR_XL1 = 0b0010_0000;
+        const ODR_XL0 = 0b0001_0000;
+        const FS1_XL = 0b0000_1000;
+        const FS0_XL = 0b0000_0100;
+        const LPF2_XL_EN = 0b0000_0010;
+    }
+
+    /// CTRL2_G (0x11)
+    /// Gyroscope control register 2 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl2G: u8 {
+        const ODR_G3 = 0b1000_0000;
+        const ODR_G2 = 0b0100_0000;
+        const ODR_G1 = 0b0010_0000;
+        const ODR_G0 = 0b0001_0000;
+        const FS1_G = 0b0000_1000;
+        const FS0_G = 0b0000_0100;
+        const FS_125 = 0b0000_0010;
+        const FS_4000 = 0b0000_0001;
+    }
+
+    /// CTRL3_C (0x12)
+    /// Control register 3 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl3C: u8 {
+        /// Reboots memory content. Default value: 0
+        /// (0: normal mode; 1: reboot memory content)
+        /// Note: the accelerometer must be ON. This bit is automatically cleared.
+        const BOOT = 0b1000_0000;
+        /// Block Data Update. Default value: 0
+        /// (0: continuous update;
+        /// 1: output registers are not updated until MSB and LSB have been read)
+        const BDU = 0b0100_0000;
+        /// Interrupt activation level. Default value: 0
+        /// (0: interrupt output pins active high; 1: interrupt output pins active low
+        const H_LACTIVE = 0b0010_0000;
+        /// Push-pull/open-drain selection on INT1 and INT2 pins. This bit must be set to '0' when H_LACTIVE is set to '1'.
+        /// Default value: 0
+        /// (0: push-pull mode; 1: open-drain mode)
+        const PP_OD = 0b0001_0000;
+        /// SPI Serial Interface Mode selection. Default value: 0
+        /// (0: 4-wire interface; 1: 3-wire interface)
+        const SIM = 0b0000_1000;
+        /// Register address automatically incremented during a multiple byte access with a serial interface (I²C or SPI).
+        /// Default value: 1
+        /// (0: disabled; 1: enabled)
+        const IF_INC = 0b0000_0100;
+        /// Software reset. Default value: 0
+        /// (0: normal mode; 1: reset device)
+        /// This bit is automatically cleared.
+        const SW_RESET = 0b0000_0001;
+    }
+
+    /// CTRL4_C (0x13)
+    /// Control register 4 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl4C: u8 {
+        const SLEEP_G = 0b0100_0000;
+        const INT2_ON_INT1 = 0b0010_0000;
+        const DRDY_MASK = 0b0000_1000;
+        const I2C_DISABLE = 0b0000_0100;
+        const LPF1_SEL_G = 0b0000_0010;
+    }
+
+    /// CTRL5_C (0x14)
+    /// Control register 5 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl5C: u8 {
+        const ROUNDING1 = 0b0100_0000;
+        const ROUNDING0 = 0b0010_0000;
+        const ST1_G = 0b0000_1000;
+        const ST0_G = 0b0000_0100;
+        const ST1_XL = 0b0000_0010;
+        const ST0_XL = 0b0000_0001;
+    }
+
+    /// CTRL6_C (0x15)
+    /// Control register 6 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl6C: u8 {
+        const TRIG_EN = 0b1000_0000;
+        const LVL1_EN = 0b0100_0000;
+        const LVL2_EN = 0b0010_0000;
+        const XL_HM_MODE = 0b0001_0000;
+        const USR_OFF_W = 0b0000_1000;
+        const FTYPE_2 = 0b0000_0100;
+        const FTYPE_1 = 0b0000_0010;
+        const FTYPE_0 = 0b0000_0001;
+    }
+
+    /// CTRL7_G (0x16)
+    /// Control register 7 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl7G: u8 {
+        const G_HM_MODE = 0b1000_0000;
+        const HP_EN_G = 0b0100_0000;
+        const HPM1_G = 0b0010_0000;
+        const HPM0_G = 0b0001_0000;
+        const OIS_ON_EN = 0b0000_0100;
+        const USR_OFF_ON_OUT = 0b0000_0010;
+        const OIS_ON = 0b0000_0001;
+    }
+
+    /// CTRL8_XL (0x17)
+    /// Control register 8 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl8Xl: u8 {
+        const HPCF_XL_2 = 0b1000_0000;
+        const HPCF_XL_1 = 0b0100_0000;
+        const HPCF_XL_0 = 0b0010_0000;
+        const HP_REF_MODE_XL = 0b0001_0000;
+        const FASTSETTL_MODE_XL = 0b0000_1000;
+        const HP_SLOPE_XL_EN = 0b0000_0100;
+        const LOW_PASS_ON_6D = 0b0000_0001;
+    }
+
+    /// CTRL9_XL (0x18)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl9Xl: u8 {
+        /// DEN value stored in LSB of X-axis. Default value: 1
+        /// (0: DEN not stored in X-axis LSB; 1: DEN stored in X-axis LSB)
+        const DEN_X = 0b1000_0000;
+        /// DEN value stored in LSB of Y-axis. Default value: 1
+        /// (0: DEN not stored in Y-axis LSB; 1: DEN stored in Y-axis LSB)
+        const DEN_Y = 0b0100_0000;
+        /// DEN value stored in LSB of Z-axis. Default value: 1
+        /// (0: DEN not stored in Z-axis LSB; 1: DEN stored in Z-axis LSB)
+        const DEN_Z = 0b0010_0000;
+        /// DEN stamping sensor selection. Default value: 0
+        /// (0: DEN pin info stamped in the gyroscope axis selected by bits [7:5];
+        /// 1: DEN pin info stamped in the accelerometer axis selected by bits [7:5])
+        const DEN_XL_G = 0b0001_0000;
+        /// Extends DEN functionality to accelerometer sensor. Default value: 0
+        /// (0: disabled; 1: enabled)
+        const DEN_XL_EN = 0b0000_1000;
+        /// DEN active level configuration. Default value: 0
+        /// (0: active low; 1: active high)
+        const DEN_LH = 0b0000_0100;
+        /// Disables MIPI I3CSM communication protocol(1)
+        /// (0: SPI, I²C, MIPI I3CSM interfaces enabled (default);
+        /// 1: MIPI I3CSM interface disabled)
+        const I3C_DISABLE = 0b0000_0010;
+    }
+
+    /// CTRL10_C (0x19)
+    /// Control register 10 (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Ctrl10C: u8 {
+        /// Enables timestamp counter. Default value: 0
+        /// (0: disabled; 1: enabled)
+        /// The counter is readable in TIMESTAMP0 (40h), TIMESTAMP1 (41h), TIMESTAMP2 (42h), and TIMESTAMP3 (43h).
+        const TIMESTAMP_EN = 0b0010_0000;
+    }
+
+    /// ALL_INT_SRC (0x1A)
+    /// Source register for all interrupts (r)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct AllIntSrc: u8 {
+        /// Alerts timestamp overflow within 6.4 ms
+        const TIMESTAMP_ENDCOUNT = 0b1000_0000;
+        /// Detects change event in activity/inactivity status.
+        const SLEEP_CHANGE_IA = 0b0010_0000;
+        /// Interrupt active for change in position of portrait, landscape, face-up, face-down.
+        const D6D_IA = 0b0001_0000;
+        /// Double-tap event status.
+        const DOUBLE_TAP = 0b0000_1000;
+        /// Single-tap event status.
+        const SINGLE_TAP = 0b0000_0100;
+        /// Wake-up event status.
+        const WU_IA = 0b0000_0010;
+        /// Free-fall event status.
+        const FF_IA = 0b0000_0001;
+    }
+
+    /// WAKE_UP_SRC (0x1B)
+    /// Wake-up interrupt source register (r)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct WakeUpSrc: u8 {
+        /// Detects change event in activity/inactivity status.
+        const SLEEP_CHANGE_IA = 0b0100_0000;
+        /// Free-fall event detection status.
+        const FF_IA = 0b0010_0000;
+        /// Sleep event status.
+        const SLEEP_STATE = 0b0001_0000;
+        /// Wake-up event detection status.
+        const WU_IA = 0b0000_1000;
+        /// Wake-up event detection status on X-axis.
+        const X_WU = 0b0000_0100;
+        /// Wake-up event detection status on Y-axis.
+        const Y_WU = 0b0000_0010;
+        /// Wake-up event detection status on Z-axis.
+        const Z_WU = 0b0000_0001;
+    }
+
+    /// TAP_SRC (0x1C)
+    /// Tap source register (r)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct TapSrc: u8 {
+        /// Tap event detection status.
+        const TAP_IA = 0b0100_0000;
+        /// Single-tap event status.
+        const SINGLE_TAP = 0b0010_0000;
+        /// Double-tap event detection status.
+        const DOUBLE_TAP = 0b0001_0000;
+        /// Sign of acceleration detected by tap event.
+        /// (0: positive sign of acceleration detected by tap event;
+        /// 1: negative sign of acceleration detected by tap event)
+        const TAP_SIGN = 0b0000_1000;
+        /// Tap event detection status on X-axis.
+        const X_TAP = 0b0000_0100;
+        /// Tap event detection status on Y-axis.
+        const Y_TAP = 0b0000_0010;
+        /// Tap event detection status on Z-axis.
+        const Z_TAP = 0b0000_0001;
+    }
+
+    /// D6D_SRC (0x1D)
+    /// Portrait, landscape, face-up and face-down source register (r)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct D6dSrc: u8 {
+        /// DEN data-ready signal.
+        const DEN_DRDY = 0b1000_0000;
+        /// Interrupt active for change position portrait, landscape, face-up, face-down.
+        const D6D_IA = 0b0100_0000;
+        /// Z-axis high event (over threshold).
+        const ZH = 0b0010_0000;
+        /// Z-axis low event (under threshold).
+        const ZL = 0b0001_0000;
+        /// Y-axis high event (over threshold).
+        const YH = 0b0000_1000;
+        /// Y-axis low event (under threshold).
+        const YL = 0b0000_0100;
+        /// X-axis high event (over threshold).
+        const XH = 0b0000_0010;
+        /// X-axis low event (under threshold).
+        const XL = 0b0000_0001;
+    }
+
+    /// STATUS_REG (0x1E)
+    /// The STATUS_REG register is read by the primary interface SPI/I²C & MIPI I3CSM (r)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct StatusReg: u8 {
+        /// Temperature new data available. Default: 0
+        /// (0: no set of data is available at temperature sensor output;
+        /// 1: a new set of data is available at temperature sensor output)
+        const TDA = 0b0000_0100;
+        /// Gyroscope new data available. Default value: 0
+        /// (0: no set of data available at gyroscope output;
+        /// 1: a new set of data is available at gyroscope output)
+        const GDA = 0b0000_0010;
+        /// Accelerometer new data available. Default value: 0
+        /// (0: no set of data available at accelerometer output;
+        /// 1: a new set of data is available at accelerometer output)
+        const XLDA = 0b0000_0001;
+    }
+
+    /// EMB_FUNC_STATUS_MAINPAGE (0x35)
+    /// Embedded function status register (r)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct EmbFuncStatusMainpage: u8 {
+        /// Interrupt status bit for FSM long counter timeout interrupt event.
+        const IS_FSM_LC = 0b1000_0000;
+        /// Interrupt status bit for significant motion detection.
+        const IS_SIGMOT = 0b0010_0000;
+        /// Interrupt status bit for tilt detection.
+        const IS_TILT = 0b0001_0000;
+        /// Interrupt status bit for step detection.
+        const IS_STEP_DET = 0b0000_1000;
+    }
+
+    /// FIFO_STATUS2 (0x3B)
+    /// FIFO status register 2 (r)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct FifoStatus2: u8 {
+        /// FIFO watermark status
+        const FIFO_WTM_IA = 0b1000_0000;
+        /// FIFO overrun status
+        const FIFO_OVR_IA = 0b0100_0000;
+        /// Smart FIFO full status
+        const FIFO_FULL_IA = 0b0010_0000;
+        const COUNTER_BDR_IA = 0b0001_0000;
+        const FIFO_OVR_LATCHED = 0b0000_1000;
+        /// 未読の FIFO ワード数の 10 ビット目
+        const DIFF_FIFO_9 = 0b0000_0010;
+        /// 未読の FIFO ワード数の 9 ビット目
+        const DIFF_FIFO_8 = 0b0000_0001;
+    }
+
+    /// TAP_CFG0 (0x56)
+    /// Activity/inactivity functions, configuration of filtering, and tap recognition functions (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct TapCfg0: u8 {
+        /// This bit allows immediately clearing the latched interrupts of an event detection upon
+        /// the read of the corresponding status register. It must be set to 1 together with LIR.
+        const INT_CLR_ON_READ = 0b0100_0000;
+        /// Activity/inactivity interrupt mode configuration.
+        const SLEEP_STATUS_ON_INT = 0b0010_0000;
+        /// HPF or SLOPE filter selection on wake-up and Activity/Inactivity functions.
+        const SLOPE_FDS = 0b0001_0000;
+        /// Enable X direction in tap recognition.
+        const TAP_X_EN = 0b0000_1000;
+        /// Enable Y direction in tap recognition.
+        const TAP_Y_EN = 0b0000_0100;
+        /// Enable Z direction in tap recognition.
+        const TAP_Z_EN = 0b0000_0010;
+        /// Latched Interrupt.
+        /// (0: interrupt request not latched; 1: interrupt request latched)
+        const LIR = 0b0000_0001;
+    }
+
+    /// TAP_CFG1 (0x57)
+    /// Tap configuration register (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct TapCfg1: u8 {
+        /// Selection of axis priority for tap detection.
+        const TAP_PRIORITY_2 = 0b1000_0000;
+        const TAP_PRIORITY_1 = 0b0100_0000;
+        const TAP_PRIORITY_0 = 0b0010_0000;
+        /// X-axis tap recognition threshold. 1 LSB = FS_XL / (2^5)
+        const TAP_THS_X_4 = 0b0001_0000;
+        const TAP_THS_X_3 = 0b0000_1000;
+        const TAP_THS_X_2 = 0b0000_0100;
+        const TAP_THS_X_1 = 0b0000_0010;
+        const TAP_THS_X_0 = 0b0000_0001;
+    }
+
+    /// TAP_CFG2 (0x58)
+    /// Enables interrupt and inactivity functions, and tap recognition functions (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct TapCfg2: u8 {
+        /// Enable basic interrupts (6D/4D, free-fall, wake-up, tap, inactivity).
+        const INTERRUPTS_ENABLE = 0b1000_0000;
+        const INACT_EN1 = 0b0100_0000;
+        const INACT_EN0 = 0b0010_0000;
+        const TAP_THS_Y_4 = 0b0001_0000;
+        const TAP_THS_Y_3 = 0b0000_1000;
+        const TAP_THS_Y_2 = 0b0000_0100;
+        const TAP_THS_Y_1 = 0b0000_0010;
+        const TAP_THS_Y_0 = 0b0000_0001;
+    }
+
+    /// TAP_THS_6D (0x59)
+    /// Portrait/landscape position and tap function threshold register (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct TapThs6d: u8 {
+        /// 4D orientation detection enable. Z-axis position detection is disabled.
+        const D4D_EN = 0b1000_0000;
+        /// Threshold for 4D/6D function.
+        const SIXD_THS_1 = 0b0100_0000;
+        const SIXD_THS_0 = 0b0010_0000;
+        /// Z-axis recognition threshold. 1 LSB = FS_XL / (2^5)
+        const TAP_THS_Z_4 = 0b0001_0000;
+        const TAP_THS_Z_3 = 0b0000_1000;
+        const TAP_THS_Z_2 = 0b0000_0100;
+        const TAP_THS_Z_1 = 0b0000_0010;
+        const TAP_THS_Z_0 = 0b0000_0001;
+    }
+
+    /// INT_DUR2 (0x5A)
+    /// Tap recognition function setting register (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct IntDur2: u8 {
+        /// Duration of maximum time gap for double tap recognition.
+        /// Default: 0000 (which is 16 * ODR_XL time). 1 LSB = 32 * ODR_XL time
+        const DUR_3 = 0b1000_0000;
+        const DUR_2 = 0b0100_0000;
+        const DUR_1 = 0b0010_0000;
+        const DUR_0 = 0b0001_0000;
+        /// Expected quiet time after a tap detection.
+        /// Default value: 00 (which is 2 * ODR_XL time). 1 LSB = 4 * ODR_XL time
+        const QUIET_1 = 0b0000_1000;
+        const QUIET_0 = 0b0000_0100;
+        /// Maximum duration of overthreshold event.
+        /// Default value: 00 (which is 4 * ODR_XL time). 1 LSB = 8 * ODR_XL time
+        const SHOCK_1 = 0b0000_0010;
+        const SHOCK_0 = 0b0000_0001;
+    }
+
+    /// WAKE_UP_THS (0x5B)
+    /// Single/double-tap selection and wake-up configuration (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct WakeUpThs: u8 {
+        /// Single/double-tap event enable.
+        /// (0: only single-tap event enabled; 1: both single and double-tap events enabled)
+        const SINGLE_DOUBLE_TAP = 0b1000_0000;
+        /// Drives the low-pass filtered data with user offset correction (instead of high-pass
+        /// filtered data) to the wakeup function.
+        const USR_OFF_ON_WU = 0b0100_0000;
+        /// Threshold for wakeup.
+        const WK_THS_5 = 0b0010_0000;
+        const WK_THS_4 = 0b0001_0000;
+        const WK_THS_3 = 0b0000_1000;
+        const WK_THS_2 = 0b0000_0100;
+        const WK_THS_1 = 0b0000_0010;
+        const WK_THS_0 = 0b0000_0001;
+    }
+
+    /// MD1_CFG (0x5E)
+    /// Functions routing on INT1 register (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Md1Cfg: u8 {
+        /// Routing of activity/inactivity recognition event on INT1.
+        const INT1_SLEEP_CHANGE = 0b1000_0000;
+        /// Routing of single-tap recognition event on INT1.
+        const INT1_SINGLE_TAP = 0b0100_0000;
+        /// Routing of wakeup event on INT1.
+        const INT1_WU = 0b0010_0000;
+        /// Routing of free-fall event on INT1.
+        const INT1_FF = 0b0001_0000;
+        /// Routing of tap event on INT1.
+        const INT1_DOUBLE_TAP = 0b0000_1000;
+        /// Routing of 6D event on INT1.
+        const INT1_6D = 0b0000_0100;
+        /// Routing of embedded functions event on INT1.
+        const INT1_EMB_FUNC = 0b0000_0010;
+        /// Routing of sensor hub communication concluded event on INT1.
+        const INT1_SHUB = 0b0000_0001;
+    }
+
+    /// MD2_CFG (0x5F)
+    /// Functions routing on INT2 register (r/w)
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+    pub struct Md2Cfg: u8 {
+        /// Routing of activity/inactivity recognition event on INT2.
+        const INT2_SLEEP_CHANGE = 0b1000_0000;
+        /// Single-tap recognition routing on INT2.
+        const INT2_SINGLE_TAP = 0b0100_0000;
+        /// Routing of wakeup event on INT2.
+        const INT2_WU = 0b0010_0000;
+        /// Routing of free-fall event on INT2.
+        const INT2_FF = 0b0001_0000;
+        /// Routing of tap event on INT2.
+        const INT2_DOUBLE_TAP = 0b0000_1000;
+        /// Routing of 6D event on INT2.
+        const INT2_6D = 0b0000_0100;
+        /// Routing of embedded functions event on INT2.
+        const INT2_EMB_FUNC = 0b0000_0010;
+        /// Enables routing on INT2 pin of the alert for timestamp overflow within 6.4 ms.
+        const INT2_TIMESTAMP = 0b0000_0001;
+    }
+}
+
+impl RegisterAddress {
+    /// Returns read address (SPI)
+    pub fn read(&self) -> u8 {
+        self.bits() | 0x80
+    }
+}
+
+/// X, Y, Z の順に並んだ出力レジスタの生の値
+#[repr(C, packed)]
+#[derive(Default, Debug, Clone, Copy)]
+struct RawAxes {
+    pub x: i16,
+    pub y: i16,
+    pub z: i16,
+}
+
+impl RawAxes {
+    fn to_acceleration(self, full_scale: AccelFullScale, timestamp: Option<u32>) -> Acceleration {
+        let sensitivity = full_scale.sensitivity();
+        Acceleration {
+            x: (self.x as f64) * sensitivity,
+            y: (self.y as f64) * sensitivity,
+            z: (self.z as f64) * sensitivity,
+            timestamp,
+        }
+    }
+
+    fn to_angular_rate(self, full_scale: GyroFullScale, timestamp: Option<u32>) -> AngularRate {
+        let sensitivity = full_scale.sensitivity();
+        AngularRate {
+            x: (self.x as f64) * sensitivity,
+            y: (self.y as f64) * sensitivity,
+            z: (self.z as f64) * sensitivity,
+            timestamp,
+        }
+    }
+}
+
+/// OUT_TEMP_L (0x20) から OUTZ_H_A (0x2D) までの生の値
+#[repr(C, packed)]
+#[derive(Default, Debug, Clone, Copy)]
+struct RawMotion {
+    pub temperature: i16,
+    pub angular_rate: RawAxes,
+    pub acceleration: RawAxes,
+}
+
+/// `#[repr(C, packed)]` な構造体をレジスタの読み出し先のバッファとして扱う
+fn as_bytes_mut<T: Copy>(value: &mut T) -> &mut [u8] {
+    unsafe {
+        core::slice::from_raw_parts_mut(value as *mut T as *mut u8, core::mem::size_of::<T>())
+    }
+}
+
+/// 温度センサの生の値を [℃] に変換する
+fn to_celsius(raw: i16) -> f64 {
+    (raw as f64) / TEMPERATURE_SENSITIVITY + TEMPERATURE_OFFSET
+}
+
+/// Device driver for [LSM6DSRX](https://www.st.com/ja/mems-and-sensors/lsm6dsrx.html)
+pub struct Lsm6sdrx<D> {
+    device: D,
+    config: Lsm6dsrxConfig,
+    compensation: Compensation,
+    verify_writes: bool,
+}
+
+impl<D> Deref for Lsm6sdrx<D> {
+    type Target = D;
+    fn deref(&self) -> &Self::Target {
+        &self.device
+    }
+}
+
+impl<D> DerefMut for Lsm6sdrx<D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.device
+    }
+}
+
+/// Implementation for [`RegisterBus`]
+mod blocking {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use bitflags::Flags;
+    use embedded_hal::delay::DelayNs;
+
+    use super::*;
+
+    impl<D> Lsm6sdrx<D>
+    where
+        D: RegisterBus,
+    {
+        /// デフォルトの設定で初期化する
+        pub fn new(
+            device: D,
+            delay: &mut impl DelayNs,
+        ) -> Result<Lsm6sdrx<D>, Lsm6dsrxError<D::Error>> {
+            Self::with_config(device, Lsm6dsrxConfig::default(), delay)
+        }
+
+        /// 設定を指定して初期化する
+        pub fn with_config(
+            device: D,
+            config: Lsm6dsrxConfig,
+            delay: &mut impl DelayNs,
+        ) -> Result<Lsm6sdrx<D>, Lsm6dsrxError<D::Error>> {
+            let mut imu = Lsm6sdrx {
+                device,
+                config,
+                compensation: Compensation::default(),
+                verify_writes: false,
+            };
+
+            // check device
+            {
+                let who_am_i = read_reg_u8(&mut imu.device, RegisterAddress::WHO_AM_I)?;
+                if who_am_i != DEFAULT_WHO_AM_I {
+                    return Err(Lsm6dsrxError::WhoAmI(who_am_i));
+                }
+            }
+
+            imu.reset(delay)?;
+
+            Ok(imu)
+        }
+
+        /// ソフトウェアリセットして、現在の設定を書き込み直す
+        ///
+        /// FIFO、割り込み、タップの設定はデフォルトに戻るので、必要なら [`Lsm6sdrx::configure_fifo`],
+        /// [`Lsm6sdrx::configure_interrupts`], [`Lsm6sdrx::configure_tap`] し直すこと
+        pub fn reset(&mut self, delay: &mut impl DelayNs) -> Result<(), Lsm6dsrxError<D::Error>> {
+            // reset device
+            // レジスタが初期値に戻るので、書き込んだ値は読み返して確認しない
+            {
+                let reg = self.read_register::<Ctrl3C>()? | Ctrl3C::SW_RESET;
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL3_C, reg.bits())?;
+            }
+            self.wait_for_clear(Ctrl3C::SW_RESET, SW_RESET_TIMEOUT_US, delay)?;
+
+            self.init()
+        }
+
+        /// メモリの内容 (トリミングパラメータ) を読み込み直してから [`Lsm6sdrx::reset`] する
+        ///
+        /// 加速度計が動いている (`accel_odr` が `PowerDown` でない) 必要がある
+        pub fn reboot_memory(
+            &mut self,
+            delay: &mut impl DelayNs,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            // reboot memory content
+            // `BOOT` は自動でクリアされるので、書き込んだ値は読み返して確認しない
+            {
+                let reg = self.read_register::<Ctrl3C>()? | Ctrl3C::BOOT;
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL3_C, reg.bits())?;
+            }
+            delay.delay_ms(BOOT_TIME_MS);
+            self.wait_for_clear(Ctrl3C::BOOT, BOOT_TIMEOUT_US, delay)?;
+
+            self.reset(delay)
+        }
+
+        /// リセット後のレジスタに、ドライバが前提とする設定と現在の設定を書き込む
+        fn init(&mut self) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.apply_writes(sequence::init(&self.config))
+        }
+
+        /// `CTRL3_C` の `flag` が 0 に戻るまで待つ
+        fn wait_for_clear(
+            &mut self,
+            flag: Ctrl3C,
+            timeout_us: u32,
+            delay: &mut impl DelayNs,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            let mut elapsed_us = 0;
+            loop {
+                let reg = self.read_register::<Ctrl3C>()?;
+                if !reg.intersects(flag) {
+                    return Ok(());
+                }
+                if elapsed_us >= timeout_us {
+                    return Err(Lsm6dsrxError::ResetTimeout);
+                }
+                delay.delay_us(RESET_POLL_INTERVAL_US);
+                elapsed_us += RESET_POLL_INTERVAL_US;
+            }
+        }
+
+        /// ビットフラグの型を持つレジスタを読む
+        pub fn read_register<R: Register>(&mut self) -> Result<R, Lsm6dsrxError<D::Error>> {
+            read_reg_u8(&mut self.device, R::ADDRESS).map(R::from_bits_retain)
+        }
+
+        /// レジスタを読み、`f` で変更して書き込む
+        ///
+        /// [`Lsm6sdrx::set_verify_writes`] で有効にした場合は書き込んだあとに読み返し、
+        /// 一致しなければ [`Lsm6dsrxError::VerifyFailed`] を返す
+        pub fn modify<R: Register>(
+            &mut self,
+            f: impl FnOnce(&mut R),
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            let mut reg = self.read_register::<R>()?;
+            f(&mut reg);
+            self.apply_write(RegisterWrite::set(R::ADDRESS, reg.bits()))
+        }
+
+        /// [`sequence`] の書き込みを1つ行う
+        fn apply_write(&mut self, write: RegisterWrite) -> Result<(), Lsm6dsrxError<D::Error>> {
+            if write.is_empty() {
+                return Ok(());
+            }
+            let current = if write.needs_read() {
+                read_reg_u8(&mut self.device, write.address)?
+            } else {
+                0
+            };
+            let value = write.value(current);
+            write_reg_u8(&mut self.device, write.address, value)?;
+
+            if self.verify_writes {
+                let actual = read_reg_u8(&mut self.device, write.address)?;
+                verify_write(write.address, value, actual)?;
+            }
+
+            Ok(())
+        }
+
+        /// [`sequence`] の書き込みを順に行う
+        fn apply_writes(
+            &mut self,
+            writes: impl IntoIterator<Item = RegisterWrite>,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            writes
+                .into_iter()
+                .try_for_each(|write| self.apply_write(write))
+        }
+
+        /// [`Lsm6sdrx::modify`] で書き込んだ値を読み返して確認するかを設定する
+        pub fn set_verify_writes(&mut self, verify: bool) {
+            self.verify_writes = verify;
+        }
+
+        /// 現在の設定を返す
+        pub fn config(&self) -> &Lsm6dsrxConfig {
+            &self.config
+        }
+
+        /// 設定をデバイスに書き込む
+        pub fn apply_config(
+            &mut self,
+            config: &Lsm6dsrxConfig,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.apply_writes(sequence::config(config))?;
+            self.config = config.clone();
+
+            Ok(())
+        }
+
+        /// 加速度計のフルスケールを変更する
+        pub fn set_accel_full_scale(
+            &mut self,
+            accel_full_scale: AccelFullScale,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.modify::<Ctrl1Xl>(|reg| reg.set_full_scale(accel_full_scale))?;
+            self.config.accel_full_scale = accel_full_scale;
+            Ok(())
+        }
+
+        /// 加速度計の出力レートを変更する
+        pub fn set_accel_odr(&mut self, odr: AccelOdr) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.modify::<Ctrl1Xl>(|reg| reg.set_odr(odr))?;
+            self.config.accel_odr = odr;
+            Ok(())
+        }
+
+        /// 加速度計の動作モードを変更する
+        pub fn set_accel_power_mode(
+            &mut self,
+            mode: PowerMode,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.modify::<Ctrl6C>(|reg| {
+                reg.set(Ctrl6C::XL_HM_MODE, mode == PowerMode::LowPowerNormal)
+            })?;
+            self.config.accel_power_mode = mode;
+            Ok(())
+        }
+
+        /// 角速度計の出力レートを変更する
+        pub fn set_gyro_odr(&mut self, odr: GyroOdr) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.modify::<Ctrl2G>(|reg| reg.set_odr(odr))?;
+            self.config.gyro_odr = odr;
+            Ok(())
+        }
+
+        /// 角速度計の動作モードを変更する
+        pub fn set_gyro_power_mode(
+            &mut self,
+            mode: PowerMode,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.modify::<Ctrl7G>(|reg| {
+                reg.set(Ctrl7G::G_HM_MODE, mode == PowerMode::LowPowerNormal)
+            })?;
+            self.config.gyro_power_mode = mode;
+            Ok(())
+        }
+
+        /// 加速度計のユーザーオフセットを返す
+        pub fn accel_user_offset(&self) -> AccelUserOffset {
+            self.config.accel_user_offset
+        }
+
+        /// 加速度計のユーザーオフセットを変更する
+        ///
+        /// 重みは `accel_offset_weight` で設定したもの
+        pub fn set_accel_user_offset(
+            &mut self,
+            offset: AccelUserOffset,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.apply_writes(sequence::user_offset(offset))?;
+            self.config.accel_user_offset = offset;
+            Ok(())
+        }
+
+        /// 静止した状態で加速度を `samples` 回読んで平均し、ずれを打ち消すユーザーオフセットを書き込む
+        ///
+        /// Z 軸が上を向いて水平に置かれている (X: 0, Y: 0, Z: +1g) とする。
+        /// オフセットがレジスタの範囲に収まらない場合は元のオフセットに戻して
+        /// [`Lsm6dsrxError::InvalidConfig`] を返す
+        pub fn calibrate_accel_offset(
+            &mut self,
+            samples: u32,
+            delay: &mut impl DelayNs,
+        ) -> Result<AccelUserOffset, Lsm6dsrxError<D::Error>> {
+            if self.config.accel_odr.frequency().is_none() || samples == 0 {
+                return Err(Lsm6dsrxError::InvalidConfig);
+            }
+
+            // オフセットを外して測る
+            let previous = self.config.accel_user_offset;
+            self.set_accel_user_offset(AccelUserOffset::default())?;
+            let mean = match self.average_acceleration(samples, delay) {
+                Ok(mean) => mean,
+                Err(e) => {
+                    self.set_accel_user_offset(previous)?;
+                    return Err(e);
+                }
+            };
+            let bias = [mean[0], mean[1], mean[2] - STANDARD_GRAVITY];
+
+            let Some(offset) = AccelUserOffset::from_bias(bias, self.config.accel_offset_weight)
+            else {
+                self.set_accel_user_offset(previous)?;
+                return Err(Lsm6dsrxError::InvalidConfig);
+            };
+            self.set_accel_user_offset(offset)?;
+
+            Ok(offset)
+        }
+
+        /// 加速度を `samples` 回読んで各軸の平均 [mg] を返す
+        ///
+        /// ユーザーオフセットは引かれた値で、[`Lsm6sdrx::accel_calibration`] の補正はかけない。
+        /// 6面キャリブレーションでは各姿勢でこれを呼び、[`SixPositionCalibration::record`] に渡す
+        pub fn average_acceleration(
+            &mut self,
+            samples: u32,
+            delay: &mut impl DelayNs,
+        ) -> Result<[f64; 3], Lsm6dsrxError<D::Error>> {
+            let Some(frequency) = self.config.accel_odr.frequency() else {
+                return Err(Lsm6dsrxError::InvalidConfig);
+            };
+            if samples == 0 {
+                return Err(Lsm6dsrxError::InvalidConfig);
+            }
+            // 1 周期より少し長く待つ
+            let interval_us = (1_000_000.0 / frequency) as u32 + 1;
+
+            // 設定を変えた直後の値を捨てる
+            for _ in 0..CALIBRATION_DISCARD_SAMPLES {
+                delay.delay_us(interval_us);
+                self.read_raw::<RawAxes>(RegisterAddress::OUTX_L_A)?;
+            }
+
+            let sensitivity = self.config.accel_full_scale.sensitivity();
+            let mut sum = [0.0; 3];
+            for _ in 0..samples {
+                delay.delay_us(interval_us);
+                let raw = self.read_raw::<RawAxes>(RegisterAddress::OUTX_L_A)?;
+                sum[0] += raw.x as f64 * sensitivity;
+                sum[1] += raw.y as f64 * sensitivity;
+                sum[2] += raw.z as f64 * sensitivity;
+            }
+            let samples = samples as f64;
+            Ok([sum[0] / samples, sum[1] / samples, sum[2] / samples])
+        }
+
+        /// 現在の加速度計の補正を返す
+        pub fn accel_calibration(&self) -> &Calibration {
+            &self.compensation.accel_calibration
+        }
+
+        /// 加速度計の補正を設定する
+        ///
+        /// 以降に取得する加速度 (FIFO を含む) に適用する
+        pub fn set_accel_calibration(&mut self, calibration: Calibration) {
+            self.compensation.accel_calibration = calibration;
+        }
+
+        /// 加速度計と角速度計のセルフテストを行う
+        ///
+        /// テストのあとは [`Lsm6sdrx::reset`] して元の設定に戻す。
+        /// 範囲外の軸があっても `Ok` を返すので、[`SelfTestReport::check`] で確認すること
+        pub fn self_test(
+            &mut self,
+            delay: &mut impl DelayNs,
+        ) -> Result<SelfTestReport, Lsm6dsrxError<D::Error>> {
+            let report = self.run_self_test(delay);
+            // 失敗しても元の設定に戻す
+            self.reset(delay)?;
+            report
+        }
+
+        fn run_self_test(
+            &mut self,
+            delay: &mut impl DelayNs,
+        ) -> Result<SelfTestReport, Lsm6dsrxError<D::Error>> {
+            // 他の設定をすべて無効にする
+            for addr in [
+                RegisterAddress::CTRL4_C,
+                RegisterAddress::CTRL5_C,
+                RegisterAddress::CTRL6_C,
+                RegisterAddress::CTRL7_G,
+                RegisterAddress::CTRL8_XL,
+                RegisterAddress::CTRL9_XL,
+                RegisterAddress::CTRL10_C,
+            ] {
+                write_reg_u8(&mut self.device, addr, 0)?;
+            }
+
+            // 加速度計: 52Hz, ±4g, 角速度計: power-down
+            {
+                let reg = AccelOdr::Hz52.bits() | AccelFullScale::G4.bits();
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL1_XL, reg.bits())?;
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL2_G, 0)?;
+            }
+            let sensitivity = AccelFullScale::G4.sensitivity();
+            let mut accel = [[0.0; 3]; 3];
+            for (i, mode) in [
+                AccelSelfTest::Normal,
+                AccelSelfTest::Positive,
+                AccelSelfTest::Negative,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                self.modify::<Ctrl5C>(|reg| {
+                    reg.remove(AccelSelfTest::MASK);
+                    reg.insert(mode.bits());
+                })?;
+                accel[i] =
+                    self.average_self_test_output(RegisterAddress::OUTX_L_A, sensitivity, delay)?;
+            }
+
+            // 加速度計: power-down, 角速度計: 208Hz, ±2000dps
+            {
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL5_C, 0)?;
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL1_XL, 0)?;
+                let reg = GyroOdr::Hz208.bits() | GyroFullScale::Dps2000.bits();
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL2_G, reg.bits())?;
+            }
+            let sensitivity = GyroFullScale::Dps2000.sensitivity();
+            let mut gyro = [[0.0; 3]; 3];
+            for (i, mode) in [
+                GyroSelfTest::Normal,
+                GyroSelfTest::Positive,
+                GyroSelfTest::Negative,
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                self.modify::<Ctrl5C>(|reg| {
+                    reg.remove(GyroSelfTest::MASK);
+                    reg.insert(mode.bits());
+                })?;
+                gyro[i] =
+                    self.average_self_test_output(RegisterAddress::OUTX_L_G, sensitivity, delay)?;
+            }
+
+            // セルフテストを無効にする
+            {
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL5_C, 0)?;
+                write_reg_u8(&mut self.device, RegisterAddress::CTRL2_G, 0)?;
+            }
+
+            Ok(SelfTestReport {
+                accel_positive: self_test::abs_diff(accel[1], accel[0]),
+                accel_negative: self_test::abs_diff(accel[2], accel[0]),
+                gyro_positive: self_test::abs_diff(gyro[1], gyro[0]),
+                gyro_negative: self_test::abs_diff(gyro[2], gyro[0]),
+            })
+        }
+
+        /// 出力が安定するまで待ってから、`addr` の 3 軸の値を [`self_test::SELF_TEST_SAMPLES`] 回読んで平均する
+        fn average_self_test_output(
+            &mut self,
+            addr: RegisterAddress,
+            sensitivity: f64,
+            delay: &mut impl DelayNs,
+        ) -> Result<[f64; 3], Lsm6dsrxError<D::Error>> {
+            delay.delay_ms(self_test::SELF_TEST_SETTLE_MS);
+            // 最初のサンプルは捨てる
+            self.read_raw::<RawAxes>(addr)?;
+
+            let mut sum = [0.0; 3];
+            for _ in 0..self_test::SELF_TEST_SAMPLES {
+                delay.delay_ms(self_test::SELF_TEST_SAMPLE_INTERVAL_MS);
+                let raw = self.read_raw::<RawAxes>(addr)?;
+                sum[0] += raw.x as f64 * sensitivity;
+                sum[1] += raw.y as f64 * sensitivity;
+                sum[2] += raw.z as f64 * sensitivity;
+            }
+
+            let samples = self_test::SELF_TEST_SAMPLES as f64;
+            Ok([sum[0] / samples, sum[1] / samples, sum[2] / samples])
+        }
+
+        /// FIFO を設定する
+        pub fn configure_fifo(
+            &mut self,
+            config: &FifoConfig,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.apply_writes(sequence::fifo(config)?)
+        }
+
+        /// FIFO の状態を取得する
+        pub fn fifo_status(&mut self) -> Result<FifoStatus, Lsm6dsrxError<D::Error>> {
+            let [status1, status2] = self.read_raw::<[u8; 2]>(RegisterAddress::FIFO_STATUS1)?;
+            Ok(FifoStatus::from_registers(
+                status1,
+                FifoStatus2::from_bits_retain(status2),
+            ))
+        }
+
+        /// FIFO に溜まっているデータをすべて読み出し、1つずつ `f` に渡す
+        ///
+        /// 読み出したワード数を返す。FIFO が溢れていた場合は、残っていたデータを読み出したあとに
+        /// [`Lsm6dsrxError::FifoOverrun`] を返す
+        pub fn drain_fifo_with<F>(&mut self, mut f: F) -> Result<usize, Lsm6dsrxError<D::Error>>
+        where
+            F: FnMut(FifoSample),
+        {
+            let mut drainer = FifoDrainer::new(self.fifo_status()?, &self.config);
+            for _ in 0..drainer.unread() {
+                let word = self.read_raw::<RawFifoWord>(RegisterAddress::FIFO_DATA_OUT_TAG)?;
+                if let Some(sample) = drainer.sample(word, &mut self.compensation) {
+                    f(sample);
+                }
+            }
+            drainer.finish()
+        }
+
+        /// FIFO に溜まっているデータをすべて読み出す
+        ///
+        /// FIFO が溢れていた場合も、読み出したデータは捨てずに [`FifoDrain::overrun`] で知らせる
+        #[cfg(feature = "alloc")]
+        pub fn drain_fifo(&mut self) -> Result<FifoDrain, Lsm6dsrxError<D::Error>> {
+            let mut samples = Vec::new();
+            let result = self.drain_fifo_with(|sample| samples.push(sample));
+            FifoDrain::new(samples, result)
+        }
+
+        /// [`RegisterAddress`] のすべてのレジスタを読み、1つずつ `f` に渡す
+        ///
+        /// 読むと状態が変わる `FIFO_DATA_OUT_*` と `*_SRC` は読まない
+        pub fn dump_registers_with<F>(&mut self, mut f: F) -> Result<(), Lsm6dsrxError<D::Error>>
+        where
+            F: FnMut(RegisterSnapshot),
+        {
+            for flag in RegisterAddress::FLAGS {
+                let address = *flag.value();
+                if !dump::is_dumpable(address) {
+                    continue;
+                }
+                let raw = read_reg_u8(&mut self.device, address)?;
+                f(RegisterSnapshot::new(flag.name(), address, raw));
+            }
+            Ok(())
+        }
+
+        /// [`RegisterAddress`] のすべてのレジスタを読む
+        #[cfg(feature = "alloc")]
+        pub fn dump_registers(&mut self) -> Result<Vec<RegisterSnapshot>, Lsm6dsrxError<D::Error>> {
+            let mut snapshots = Vec::new();
+            self.dump_registers_with(|snapshot| snapshots.push(snapshot))?;
+            Ok(snapshots)
+        }
+
+        /// 割り込みピンを設定する
+        ///
+        /// 組み込み機能の割り込みは、組み込み機能の側でも有効にしておく必要がある
+        pub fn configure_interrupts(
+            &mut self,
+            config: &InterruptConfig,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.apply_writes(sequence::interrupts(config)?)
+        }
+
+        /// 発生している割り込みの要因を読む
+        ///
+        /// latched の場合、読んだイベントはクリアされる
+        pub fn interrupt_sources(&mut self) -> Result<InterruptSources, Lsm6dsrxError<D::Error>> {
+            let sources = self.read_raw::<[u8; 5]>(RegisterAddress::ALL_INT_SRC)?;
+            let mainpage = self.read_raw::<[u8; 7]>(RegisterAddress::EMB_FUNC_STATUS_MAINPAGE)?;
+            Ok(InterruptSources::from_registers(sources, mainpage))
+        }
+
+        /// タップの検出を設定する
+        ///
+        /// 検出したタップをピンに出すには [`Lsm6sdrx::configure_interrupts`] で割り当てる
+        pub fn configure_tap(&mut self, config: &TapConfig) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.apply_writes(sequence::tap(config)?)
+        }
+
+        /// `TAP_SRC` を読んで、検出したタップを返す
+        ///
+        /// 検出していなければ `None` を返す。latched の場合、読んだイベントはクリアされる
+        pub fn tap_event(&mut self) -> Result<Option<TapEvent>, Lsm6dsrxError<D::Error>> {
+            let source = read_reg_u8(&mut self.device, RegisterAddress::TAP_SRC)
+                .map(TapSrc::from_bits_retain)?;
+            let timestamp = self.sample_timestamp()?;
+            Ok(source.event(timestamp))
+        }
+
+        /// `STATUS_REG` を読む
+        pub fn status(&mut self) -> Result<StatusReg, Lsm6dsrxError<D::Error>> {
+            read_reg_u8(&mut self.device, RegisterAddress::STATUS_REG)
+                .map(StatusReg::from_bits_retain)
+        }
+
+        /// 新しい加速度が出力されるまで待つ
+        ///
+        /// `timeout_us` 待っても出力されなければ [`Lsm6dsrxError::DataReadyTimeout`] を返す
+        pub fn wait_for_accel_ready(
+            &mut self,
+            timeout_us: u32,
+            delay: &mut impl DelayNs,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.wait_for_data_ready(StatusReg::XLDA, timeout_us, delay)
+        }
+
+        /// 新しい角速度が出力されるまで待つ
+        ///
+        /// `timeout_us` 待っても出力されなければ [`Lsm6dsrxError::DataReadyTimeout`] を返す
+        pub fn wait_for_gyro_ready(
+            &mut self,
+            timeout_us: u32,
+            delay: &mut impl DelayNs,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.wait_for_data_ready(StatusReg::GDA, timeout_us, delay)
+        }
+
+        /// `STATUS_REG` の `flags` がすべて立つまで待つ
+        fn wait_for_data_ready(
+            &mut self,
+            flags: StatusReg,
+            timeout_us: u32,
+            delay: &mut impl DelayNs,
+        ) -> Result<(), Lsm6dsrxError<D::Error>> {
+            let mut elapsed_us = 0;
+            loop {
+                if self.status()?.contains(flags) {
+                    return Ok(());
+                }
+                if elapsed_us >= timeout_us {
+                    return Err(Lsm6dsrxError::DataReadyTimeout);
+                }
+                delay.delay_us(DATA_READY_POLL_INTERVAL_US);
+                elapsed_us += DATA_READY_POLL_INTERVAL_US;
+            }
+        }
+
+        /// 前回読んでから新しい加速度が出力されていれば取得する
+        ///
+        /// 出力されていなければ `None` を返す
+        pub fn try_fetch_acceleration(
+            &mut self,
+        ) -> Result<Option<Acceleration>, Lsm6dsrxError<D::Error>> {
+            if !self.status()?.contains(StatusReg::XLDA) {
+                return Ok(None);
+            }
+            self.fetch_acceleration().map(Some)
+        }
+
+        /// 前回読んでから新しい角速度が出力されていれば取得する
+        ///
+        /// 出力されていなければ `None` を返す
+        pub fn try_fetch_angular_rate(
+            &mut self,
+        ) -> Result<Option<AngularRate>, Lsm6dsrxError<D::Error>> {
+            if !self.status()?.contains(StatusReg::GDA) {
+                return Ok(None);
+            }
+            self.fetch_angular_rate().map(Some)
+        }
+
+        /// 加速度と角速度の両方が新しく出力されていれば [`Lsm6sdrx::fetch_motion`] する
+        ///
+        /// どちらかが出力されていなければ `None` を返す
+        pub fn try_fetch_motion(
+            &mut self,
+        ) -> Result<Option<MotionSample>, Lsm6dsrxError<D::Error>> {
+            if !self.status()?.contains(StatusReg::XLDA | StatusReg::GDA) {
+                return Ok(None);
+            }
+            self.fetch_motion().map(Some)
+        }
+
+        /// 加速度を取得する
+        ///
+        /// [`Lsm6sdrx::accel_calibration`] で補正した値を返す
+        pub fn fetch_acceleration(&mut self) -> Result<Acceleration, Lsm6dsrxError<D::Error>> {
+            let buffer = self.read_raw::<RawAxes>(RegisterAddress::OUTX_L_A)?;
+            let timestamp = self.sample_timestamp()?;
+            let acceleration = buffer.to_acceleration(self.config.accel_full_scale, timestamp);
+            Ok(self.compensation.acceleration(acceleration))
+        }
+
+        /// 角速度を取得する
+        ///
+        /// 推定したゼロレートバイアスを引いた値を返す
+        pub fn fetch_angular_rate(&mut self) -> Result<AngularRate, Lsm6dsrxError<D::Error>> {
+            let buffer = self.read_raw::<RawAxes>(RegisterAddress::OUTX_L_G)?;
+            let timestamp = self.sample_timestamp()?;
+            let angular_rate = buffer.to_angular_rate(self.config.gyro_full_scale, timestamp);
+            Ok(self.compensation.angular_rate(angular_rate))
+        }
+
+        /// 温度を取得する
+        pub fn fetch_temperature(&mut self) -> Result<Temperature, Lsm6dsrxError<D::Error>> {
+            let buffer = self.read_raw::<i16>(RegisterAddress::OUT_TEMP_L)?;
+            Ok(Temperature {
+                celsius: to_celsius(buffer),
+            })
+        }
+
+        /// 温度、角速度、加速度を1回のトランザクションでまとめて取得する
+        ///
+        /// `BDU` が有効なので、読み終わるまで出力レジスタは更新されず、すべて同じ出力周期の値になる。
+        /// 加速度は補正をかけ、読んだ値でゼロレートバイアスの推定を更新し、角速度はバイアスを引いた値を返す
+        pub fn fetch_motion(&mut self) -> Result<MotionSample, Lsm6dsrxError<D::Error>> {
+            let buffer = self.read_raw::<RawMotion>(RegisterAddress::OUT_TEMP_L)?;
+            let timestamp = self.sample_timestamp()?;
+            let (acceleration, angular_rate) = self.compensation.motion(
+                buffer
+                    .acceleration
+                    .to_acceleration(self.config.accel_full_scale, timestamp),
+                buffer
+                    .angular_rate
+                    .to_angular_rate(self.config.gyro_full_scale, timestamp),
+            );
+            Ok(MotionSample {
+                acceleration,
+                angular_rate,
+                temperature: to_celsius(buffer.temperature),
+            })
+        }
+
+        /// 現在のゼロレートバイアスと信頼度を返す
+        pub fn gyro_bias(&self) -> GyroBias {
+            self.compensation.gyro_bias.bias()
+        }
+
+        /// ゼロレートバイアスの推定器を返す
+        pub fn gyro_bias_estimator_mut(&mut self) -> &mut GyroBiasEstimator {
+            &mut self.compensation.gyro_bias
+        }
+
+        /// タイムスタンプカウンタを有効/無効にする
+        ///
+        /// 有効にすると、取得した加速度と角速度にタイムスタンプが付く。[`Lsm6dsrxConfig::timestamp`] を変更するので、リセット後も有効なまま
+        pub fn enable_timestamp(&mut self, enable: bool) -> Result<(), Lsm6dsrxError<D::Error>> {
+            self.apply_write(sequence::timestamp(enable))?;
+            self.config.timestamp = enable;
+            Ok(())
+        }
+
+        /// タイムスタンプカウンタの値を取得する
+        ///
+        /// 1 LSB は [`schema::TIMESTAMP_RESOLUTION_US`] µs
+        pub fn fetch_timestamp(&mut self) -> Result<u32, Lsm6dsrxError<D::Error>> {
+            self.read_raw::<u32>(RegisterAddress::TIMESTAMP0)
+        }
+
+        /// タイムスタンプカウンタを 0 に戻す
+        pub fn reset_timestamp(&mut self) -> Result<(), Lsm6dsrxError<D::Error>> {
+            write_reg_u8(
+                &mut self.device,
+                RegisterAddress::TIMESTAMP2,
+                TIMESTAMP_RESET,
+            )?;
+            Ok(())
+        }
+
+        /// タイムスタンプカウンタが有効なら、出力レジスタを読んだ直後のタイムスタンプを返す
+        fn sample_timestamp(&mut self) -> Result<Option<u32>, Lsm6dsrxError<D::Error>> {
+            if !self.config.timestamp {
+                return Ok(None);
+            }
+            self.fetch_timestamp().map(Some)
+        }
+
+        /// `addr` から連続したレジスタを `T` として読む
+        ///
+        /// `T` はレジスタの並びと一致する `#[repr(C, packed)]` な構造体であること
+        fn read_raw<T: Default + Copy>(
+            &mut self,
+            addr: RegisterAddress,
+        ) -> Result<T, Lsm6dsrxError<D::Error>> {
+            let mut value = T::default();
+            let buffer = as_bytes_mut(&mut value);
+            self.device
+                .read_registers(addr, buffer)
+                .map_err(Lsm6dsrxError::Bus)?;
+            debug!("buffer = {:?}", buffer);
+
+            Ok(value)
+        }
+    }
+
+    #[inline]
+    fn read_reg_u8<D: RegisterBus>(
+        device: &mut D,
+        addr: RegisterAddress,
+    ) -> Result<u8, Lsm6dsrxError<D::Error>> {
+        let mut read_buf = [u8::MIN];
+        device
+            .read_registers(addr, &mut read_buf)
+            .map_err(Lsm6dsrxError::Bus)?;
+        Ok(read_buf[0])
+    }
+
+    #[inline]
+    fn write_reg_u8<D: RegisterBus>(
+        device: &mut D,
+        addr: RegisterAddress,
+        data: u8,
+    ) -> Result<(), Lsm6dsrxError<D::Error>> {
+        device
+            .write_register(addr, data)
+            .map_err(Lsm6dsrxError::Bus)
+    }
+}
@@ -0,0 +1,140 @@
+//! ビットフラグの型を持つレジスタ
+//!
+//! 複数ビットのフィールドは、1ビットずつ操作せずに型付きのアクセサで読み書きする
+
+use bitflags::Flags;
+
+use super::{
+    AccelFilterCutoff, AccelFullScale, AccelOdr, CounterBdrReg1, Ctrl10C, Ctrl1Xl, Ctrl2G, Ctrl3C,
+    Ctrl4C, Ctrl5C, Ctrl6C, Ctrl7G, Ctrl8Xl, Ctrl9Xl, FifoCtrl2, FifoCtrl3, FifoCtrl4,
+    GyroFullScale, GyroLpf1Bandwidth, GyroOdr, Int1Ctrl, Int2Ctrl, IntDur2, Lsm6dsrxError, Md1Cfg,
+    Md2Cfg, RegisterAddress, TapCfg0, TapCfg1, TapCfg2, TapThs6d, WakeUpThs,
+};
+
+/// 読み書きできる、ビットフラグの型を持つレジスタ
+pub trait Register: Flags<Bits = u8> + Copy {
+    /// レジスタのアドレス
+    const ADDRESS: RegisterAddress;
+}
+
+/// `address` に書き込んだ値 `expected` と読み返した値 `actual` を比べる
+pub(super) fn verify_write<E>(
+    address: RegisterAddress,
+    expected: u8,
+    actual: u8,
+) -> Result<(), Lsm6dsrxError<E>> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(Lsm6dsrxError::VerifyFailed {
+            address: address.bits(),
+            expected,
+            actual,
+        })
+    }
+}
+
+macro_rules! impl_register {
+    ($($ty:ty => $address:ident),* $(,)?) => {
+        $(
+            impl Register for $ty {
+                const ADDRESS: RegisterAddress = RegisterAddress::$address;
+            }
+        )*
+    };
+}
+
+impl_register! {
+    FifoCtrl2 => FIFO_CTRL2,
+    FifoCtrl3 => FIFO_CTRL3,
+    FifoCtrl4 => FIFO_CTRL4,
+    CounterBdrReg1 => COUNTER_BDR_REG1,
+    Int1Ctrl => INT1_CTRL,
+    Int2Ctrl => INT2_CTRL,
+    Ctrl1Xl => CTRL1_XL,
+    Ctrl2G => CTRL2_G,
+    Ctrl3C => CTRL3_C,
+    Ctrl4C => CTRL4_C,
+    Ctrl5C => CTRL5_C,
+    Ctrl6C => CTRL6_C,
+    Ctrl7G => CTRL7_G,
+    Ctrl8Xl => CTRL8_XL,
+    Ctrl9Xl => CTRL9_XL,
+    Ctrl10C => CTRL10_C,
+    TapCfg0 => TAP_CFG0,
+    TapCfg1 => TAP_CFG1,
+    TapCfg2 => TAP_CFG2,
+    TapThs6d => TAP_THS_6D,
+    IntDur2 => INT_DUR2,
+    WakeUpThs => WAKE_UP_THS,
+    Md1Cfg => MD1_CFG,
+    Md2Cfg => MD2_CFG,
+}
+
+impl Ctrl1Xl {
+    /// `ODR_XL[3:0]` (予約済みの値なら `None`)
+    pub fn odr(&self) -> Option<AccelOdr> {
+        AccelOdr::from_bits(*self)
+    }
+
+    pub fn set_odr(&mut self, odr: AccelOdr) {
+        self.remove(AccelOdr::MASK);
+        self.insert(odr.bits());
+    }
+
+    /// `FS[1:0]_XL`
+    pub fn full_scale(&self) -> AccelFullScale {
+        AccelFullScale::from_bits(*self)
+    }
+
+    pub fn set_full_scale(&mut self, full_scale: AccelFullScale) {
+        self.remove(AccelFullScale::MASK);
+        self.insert(full_scale.bits());
+    }
+}
+
+impl Ctrl2G {
+    /// `ODR_G[3:0]` (予約済みの値なら `None`)
+    pub fn odr(&self) -> Option<GyroOdr> {
+        GyroOdr::from_bits(*self)
+    }
+
+    pub fn set_odr(&mut self, odr: GyroOdr) {
+        self.remove(GyroOdr::MASK);
+        self.insert(odr.bits());
+    }
+
+    /// `FS[1:0]_G`, `FS_125`, `FS_4000` (組み合わせが不正なら `None`)
+    pub fn full_scale(&self) -> Option<GyroFullScale> {
+        GyroFullScale::from_bits(*self)
+    }
+
+    pub fn set_full_scale(&mut self, full_scale: GyroFullScale) {
+        self.remove(GyroFullScale::MASK);
+        self.insert(full_scale.bits());
+    }
+}
+
+impl Ctrl6C {
+    /// `FTYPE[2:0]`
+    pub fn ftype(&self) -> GyroLpf1Bandwidth {
+        GyroLpf1Bandwidth::from_bits(*self)
+    }
+
+    pub fn set_ftype(&mut self, ftype: GyroLpf1Bandwidth) {
+        self.remove(GyroLpf1Bandwidth::MASK);
+        self.insert(ftype.bits());
+    }
+}
+
+impl Ctrl8Xl {
+    /// `HPCF_XL_[2:0]`
+    pub fn hpcf(&self) -> AccelFilterCutoff {
+        AccelFilterCutoff::from_bits(*self)
+    }
+
+    pub fn set_hpcf(&mut self, hpcf: AccelFilterCutoff) {
+        self.remove(AccelFilterCutoff::MASK);
+        self.insert(hpcf.bits());
+    }
+}
@@ -0,0 +1,124 @@
+//! セルフテスト
+//!
+//! 6.6 Self-test / AN5358 のセルフテストの手順
+
+use serde::{Deserialize, Serialize};
+
+use super::{Ctrl5C, Lsm6dsrxError};
+
+/// セルフテストで平均をとるサンプル数
+pub(super) const SELF_TEST_SAMPLES: usize = 5;
+
+/// 設定を変えてから出力が安定するまで待つ時間 [ms]
+pub(super) const SELF_TEST_SETTLE_MS: u32 = 100;
+
+/// サンプルを読む間隔 (52Hz の 1 周期より長くする) [ms]
+pub(super) const SELF_TEST_SAMPLE_INTERVAL_MS: u32 = 20;
+
+/// 加速度計のセルフテストの出力変化の最小値 [mg]
+pub const ACCEL_SELF_TEST_MIN: f64 = 40.0;
+
+/// 加速度計のセルフテストの出力変化の最大値 [mg]
+pub const ACCEL_SELF_TEST_MAX: f64 = 1700.0;
+
+/// 角速度計のセルフテストの出力変化の最小値 (±2000dps) [mdps]
+pub const GYRO_SELF_TEST_MIN: f64 = 150_000.0;
+
+/// 角速度計のセルフテストの出力変化の最大値 (±2000dps) [mdps]
+pub const GYRO_SELF_TEST_MAX: f64 = 700_000.0;
+
+/// 加速度計のセルフテストのモード (`ST_XL[1:0]`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum AccelSelfTest {
+    Normal,
+    Positive,
+    Negative,
+}
+
+impl AccelSelfTest {
+    pub(super) const MASK: Ctrl5C = Ctrl5C::ST1_XL.union(Ctrl5C::ST0_XL);
+
+    /// `CTRL5_C` の `ST_XL[1:0]` に設定する値
+    pub(super) fn bits(self) -> Ctrl5C {
+        match self {
+            AccelSelfTest::Normal => Ctrl5C::empty(),
+            AccelSelfTest::Positive => Ctrl5C::ST0_XL,
+            AccelSelfTest::Negative => Ctrl5C::ST1_XL,
+        }
+    }
+}
+
+/// 角速度計のセルフテストのモード (`ST_G[1:0]`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum GyroSelfTest {
+    Normal,
+    Positive,
+    Negative,
+}
+
+impl GyroSelfTest {
+    pub(super) const MASK: Ctrl5C = Ctrl5C::ST1_G.union(Ctrl5C::ST0_G);
+
+    /// `CTRL5_C` の `ST_G[1:0]` に設定する値
+    pub(super) fn bits(self) -> Ctrl5C {
+        match self {
+            GyroSelfTest::Normal => Ctrl5C::empty(),
+            GyroSelfTest::Positive => Ctrl5C::ST0_G,
+            GyroSelfTest::Negative => Ctrl5C::ST1_G | Ctrl5C::ST0_G,
+        }
+    }
+}
+
+/// セルフテストの結果
+///
+/// 各軸の値は、セルフテストを有効にしたときと無効にしたときの出力の差の絶対値
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SelfTestReport {
+    /// 正の方向に力をかけたときの加速度の変化 [mg]
+    pub accel_positive: [f64; 3],
+    /// 負の方向に力をかけたときの加速度の変化 [mg]
+    pub accel_negative: [f64; 3],
+    /// 正の方向に回転をかけたときの角速度の変化 [mdps]
+    pub gyro_positive: [f64; 3],
+    /// 負の方向に回転をかけたときの角速度の変化 [mdps]
+    pub gyro_negative: [f64; 3],
+}
+
+impl SelfTestReport {
+    /// 加速度計のすべての軸が範囲内
+    pub fn accel_passed(&self) -> bool {
+        self.accel_positive
+            .iter()
+            .chain(&self.accel_negative)
+            .all(|delta| (ACCEL_SELF_TEST_MIN..=ACCEL_SELF_TEST_MAX).contains(delta))
+    }
+
+    /// 角速度計のすべての軸が範囲内
+    pub fn gyro_passed(&self) -> bool {
+        self.gyro_positive
+            .iter()
+            .chain(&self.gyro_negative)
+            .all(|delta| (GYRO_SELF_TEST_MIN..=GYRO_SELF_TEST_MAX).contains(delta))
+    }
+
+    /// 加速度計と角速度計の両方が範囲内
+    pub fn passed(&self) -> bool {
+        self.accel_passed() && self.gyro_passed()
+    }
+
+    /// 範囲外の軸があれば [`Lsm6dsrxError::SelfTestFailed`] を返す
+    pub fn check<E>(&self) -> Result<(), Lsm6dsrxError<E>> {
+        if self.passed() {
+            Ok(())
+        } else {
+            Err(Lsm6dsrxError::SelfTestFailed)
+        }
+    }
+}
+
+/// 2つの平均値の各軸の差の絶対値
+pub(super) fn abs_diff(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    // no_std では f64::abs が使えない
+    let abs = |value: f64| if value < 0.0 { -value } else { value };
+    [abs(a[0] - b[0]), abs(a[1] - b[1]), abs(a[2] - b[2])]
+}
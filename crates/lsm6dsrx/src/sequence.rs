@@ -0,0 +1,268 @@
+//! 設定を書き込むレジスタの手順
+//!
+//! 同期版と非同期版のドライバで同じ順に同じ値を書き込むように、設定をレジスタへの書き込みの並びに
+//! 変換するところまでをここで行い、ドライバは [`RegisterWrite`] を順にバスに書き込むだけにする
+
+use super::{
+    AccelBatchRate, AccelUserOffset, CounterBdrReg1, Ctrl10C, Ctrl1Xl, Ctrl2G, Ctrl3C, Ctrl4C,
+    Ctrl6C, Ctrl7G, Ctrl8Xl, Ctrl9Xl, FifoConfig, FifoCtrl2, FifoCtrl3, FifoCtrl4, FifoMode,
+    GyroBatchRate, Int1Ctrl, Int2Ctrl, IntDur2, InterruptConfig, InterruptPinMode,
+    InterruptPolarity, InterruptRoute, InterruptSignal, Lsm6dsrxConfig, Lsm6dsrxError, Md1Cfg,
+    Md2Cfg, OffsetWeight, PowerMode, Register, RegisterAddress, TapCfg0, TapCfg1, TapCfg2,
+    TapConfig, TapMode, TapPriority, TapThs6d, TemperatureBatchRate, TimestampBatch, WakeUpThs,
+    FIFO_WATERMARK_MAX,
+};
+
+/// 1つのレジスタへの書き込み
+///
+/// `mask` のビットだけを `bits` にし、ほかのビットは読んだ値のままにする
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct RegisterWrite {
+    pub address: RegisterAddress,
+    mask: u8,
+    bits: u8,
+}
+
+impl RegisterWrite {
+    /// レジスタ全体を書き込む
+    pub fn set(address: RegisterAddress, value: u8) -> Self {
+        RegisterWrite {
+            address,
+            mask: u8::MAX,
+            bits: value,
+        }
+    }
+
+    /// 読んだ値を `f` で変更して書き込む
+    ///
+    /// `f` はビットを立てるか落とすかだけで、読んだ値によって書き込む値を変えないこと
+    pub fn modify<R: Register>(f: impl Fn(&mut R)) -> Self {
+        let apply = |bits| {
+            let mut reg = R::from_bits_retain(bits);
+            f(&mut reg);
+            reg.bits()
+        };
+        // 0 から立ったビットと、すべて立った状態から落ちたビットが `f` で決まるビット
+        let bits = apply(0);
+        RegisterWrite {
+            address: R::ADDRESS,
+            mask: bits | !apply(u8::MAX),
+            bits,
+        }
+    }
+
+    /// 何も変更しない
+    pub fn is_empty(&self) -> bool {
+        self.mask == 0
+    }
+
+    /// 書き込む前にレジスタを読む必要がある
+    pub fn needs_read(&self) -> bool {
+        self.mask != u8::MAX
+    }
+
+    /// 読んだ値 `current` に対して書き込む値
+    pub fn value(&self, current: u8) -> u8 {
+        current & !self.mask | self.bits
+    }
+}
+
+/// リセット後のレジスタに、ドライバが前提とする設定と `config` を書き込む
+pub(super) fn init(config: &Lsm6dsrxConfig) -> impl Iterator<Item = RegisterWrite> {
+    [
+        // I3C を無効化
+        RegisterWrite::modify::<Ctrl9Xl>(|reg| reg.insert(Ctrl9Xl::I3C_DISABLE)),
+        // 読みだしているレジスタは更新しない
+        RegisterWrite::modify::<Ctrl3C>(|reg| reg.insert(Ctrl3C::BDU)),
+        // オフセットを有効
+        RegisterWrite::modify::<Ctrl7G>(|reg| reg.insert(Ctrl7G::USR_OFF_ON_OUT)),
+    ]
+    .into_iter()
+    .chain(self::config(config))
+}
+
+/// `config` を書き込む
+pub(super) fn config(config: &Lsm6dsrxConfig) -> impl Iterator<Item = RegisterWrite> {
+    [
+        // 加速度計の設定
+        RegisterWrite::modify::<Ctrl1Xl>(|reg| {
+            reg.set_odr(config.accel_odr);
+            reg.set_full_scale(config.accel_full_scale);
+            reg.set(Ctrl1Xl::LPF2_XL_EN, config.accel_lpf2);
+        }),
+        // フィルタのカットオフを設定
+        RegisterWrite::modify::<Ctrl8Xl>(|reg| reg.set_hpcf(config.accel_filter_cutoff)),
+        // 加速度計の動作モード、オフセットの重み、角速度計の LPF1 の帯域を設定
+        RegisterWrite::modify::<Ctrl6C>(|reg| {
+            reg.set(
+                Ctrl6C::XL_HM_MODE,
+                config.accel_power_mode == PowerMode::LowPowerNormal,
+            );
+            reg.set(
+                Ctrl6C::USR_OFF_W,
+                config.accel_offset_weight == OffsetWeight::Pow2Minus6,
+            );
+            reg.set_ftype(config.gyro_lpf1_bandwidth);
+        }),
+        // 角速度計の動作モードを設定
+        RegisterWrite::modify::<Ctrl7G>(|reg| {
+            reg.set(
+                Ctrl7G::G_HM_MODE,
+                config.gyro_power_mode == PowerMode::LowPowerNormal,
+            )
+        }),
+        // 角速度計の設定
+        RegisterWrite::modify::<Ctrl2G>(|reg| {
+            reg.set_odr(config.gyro_odr);
+            reg.set_full_scale(config.gyro_full_scale);
+        }),
+        // LPF1 を設定
+        RegisterWrite::modify::<Ctrl4C>(|reg| reg.set(Ctrl4C::LPF1_SEL_G, config.gyro_lpf1)),
+    ]
+    .into_iter()
+    // 加速度計のユーザーオフセットを設定
+    .chain(user_offset(config.accel_user_offset))
+    .chain([timestamp(config.timestamp)])
+}
+
+/// `X_OFS_USR`, `Y_OFS_USR`, `Z_OFS_USR` を書き込む
+pub(super) fn user_offset(offset: AccelUserOffset) -> [RegisterWrite; 3] {
+    [
+        RegisterWrite::set(RegisterAddress::X_OFS_USR, offset.x as u8),
+        RegisterWrite::set(RegisterAddress::Y_OFS_USR, offset.y as u8),
+        RegisterWrite::set(RegisterAddress::Z_OFS_USR, offset.z as u8),
+    ]
+}
+
+/// タイムスタンプカウンタを有効/無効にする
+pub(super) fn timestamp(enable: bool) -> RegisterWrite {
+    RegisterWrite::modify::<Ctrl10C>(|reg| reg.set(Ctrl10C::TIMESTAMP_EN, enable))
+}
+
+/// FIFO を設定する
+pub(super) fn fifo<E>(config: &FifoConfig) -> Result<[RegisterWrite; 4], Lsm6dsrxError<E>> {
+    if config.watermark > FIFO_WATERMARK_MAX {
+        return Err(Lsm6dsrxError::InvalidConfig);
+    }
+
+    Ok([
+        // watermark を設定
+        RegisterWrite::set(RegisterAddress::FIFO_CTRL1, config.watermark as u8),
+        RegisterWrite::modify::<FifoCtrl2>(|reg| {
+            reg.set(FifoCtrl2::WTM8, config.watermark & 0x100 != 0);
+            reg.set(FifoCtrl2::STOP_ON_WTM, config.stop_on_watermark);
+        }),
+        // batch data rate を設定
+        RegisterWrite::modify::<FifoCtrl3>(|reg| {
+            reg.remove(AccelBatchRate::MASK);
+            reg.insert(config.accel_batch_rate.bits());
+            reg.remove(GyroBatchRate::MASK);
+            reg.insert(config.gyro_batch_rate.bits());
+        }),
+        // タイムスタンプ、温度、FIFO のモードを設定
+        RegisterWrite::modify::<FifoCtrl4>(|reg| {
+            reg.remove(TimestampBatch::MASK);
+            reg.insert(config.timestamp_batch.bits());
+            reg.remove(TemperatureBatchRate::MASK);
+            reg.insert(config.temperature_batch_rate.bits());
+            reg.remove(FifoMode::MASK);
+            reg.insert(config.mode.bits());
+        }),
+    ])
+}
+
+/// 割り込みピンを設定する
+pub(super) fn interrupts<E>(
+    config: &InterruptConfig,
+) -> Result<[RegisterWrite; 8], Lsm6dsrxError<E>> {
+    if !config.is_valid() {
+        return Err(Lsm6dsrxError::InvalidConfig);
+    }
+
+    Ok([
+        // ピンの極性と出力を設定
+        RegisterWrite::modify::<Ctrl3C>(|reg| {
+            reg.remove(InterruptPolarity::MASK);
+            reg.insert(config.polarity.bits());
+            reg.remove(InterruptPinMode::MASK);
+            reg.insert(config.pin_mode.bits());
+        }),
+        // latched か pulsed かを設定
+        RegisterWrite::modify::<TapCfg0>(|reg| {
+            reg.set(
+                TapCfg0::LIR,
+                config.event_signal == InterruptSignal::Latched,
+            )
+        }),
+        RegisterWrite::modify::<CounterBdrReg1>(|reg| {
+            reg.set(
+                CounterBdrReg1::DATAREADY_PULSED,
+                config.data_ready_signal == InterruptSignal::Pulsed,
+            )
+        }),
+        // イベントをピンに割り当てる
+        RegisterWrite::modify::<Int1Ctrl>(|reg| {
+            reg.remove(InterruptRoute::INT1_CTRL_MASK);
+            reg.insert(config.int1.int1_ctrl());
+        }),
+        RegisterWrite::modify::<Md1Cfg>(|reg| {
+            reg.remove(InterruptRoute::MD1_CFG_MASK);
+            reg.insert(config.int1.md1_cfg());
+        }),
+        RegisterWrite::modify::<Int2Ctrl>(|reg| {
+            reg.remove(InterruptRoute::INT2_CTRL_MASK);
+            reg.insert(config.int2.int2_ctrl());
+        }),
+        RegisterWrite::modify::<Md2Cfg>(|reg| {
+            reg.remove(InterruptRoute::MD2_CFG_MASK);
+            reg.insert(config.int2.md2_cfg());
+        }),
+        // タップ、wake-up などの基本の割り込みを有効にする
+        // ピンに出さずに `configure_tap` だけで検出していることもあるので、使わないときもクリアはしない
+        RegisterWrite::modify::<TapCfg2>(|reg| {
+            if config.uses_basic_interrupts() {
+                reg.insert(TapCfg2::INTERRUPTS_ENABLE);
+            }
+        }),
+    ])
+}
+
+/// タップの検出を設定する
+pub(super) fn tap<E>(config: &TapConfig) -> Result<[RegisterWrite; 7], Lsm6dsrxError<E>> {
+    if !config.is_valid() {
+        return Err(Lsm6dsrxError::InvalidConfig);
+    }
+
+    Ok([
+        // 検出する軸と閾値
+        RegisterWrite::modify::<TapCfg0>(|reg| {
+            reg.remove(TapConfig::TAP_CFG0_MASK);
+            reg.insert(config.tap_cfg0());
+        }),
+        RegisterWrite::modify::<TapCfg1>(|reg| {
+            reg.remove(TapPriority::MASK | TapConfig::X_THRESHOLD_MASK);
+            reg.insert(config.tap_cfg1());
+        }),
+        RegisterWrite::modify::<TapCfg2>(|reg| {
+            reg.remove(TapConfig::Y_THRESHOLD_MASK);
+            reg.insert(config.tap_cfg2());
+        }),
+        RegisterWrite::modify::<TapThs6d>(|reg| {
+            reg.remove(TapConfig::Z_THRESHOLD_MASK);
+            reg.insert(config.tap_ths_6d());
+        }),
+        // 時間の窓はレジスタ全体
+        RegisterWrite::modify::<IntDur2>(|reg| *reg = config.int_dur2()),
+        RegisterWrite::modify::<WakeUpThs>(|reg| {
+            reg.set(
+                WakeUpThs::SINGLE_DOUBLE_TAP,
+                config.mode == TapMode::SingleAndDouble,
+            )
+        }),
+        RegisterWrite::modify::<TapCfg2>(|reg| {
+            if config.is_enabled() {
+                reg.insert(TapCfg2::INTERRUPTS_ENABLE);
+            }
+        }),
+    ])
+}
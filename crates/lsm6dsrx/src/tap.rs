@@ -0,0 +1,249 @@
+//! タップ、ダブルタップの検出
+
+use schema::{TapAxis, TapEvent, TapKind, TapSign};
+use serde::{Deserialize, Serialize};
+
+use super::{IntDur2, TapCfg0, TapCfg1, TapCfg2, TapSrc, TapThs6d};
+
+/// タップの閾値の最大値 [LSB] (1 LSB = フルスケール / 32)
+pub const TAP_THRESHOLD_MAX: u8 = 0x1F;
+
+/// `SHOCK[1:0]` の最大値
+pub const TAP_SHOCK_MAX: u8 = 0b11;
+
+/// `QUIET[1:0]` の最大値
+pub const TAP_QUIET_MAX: u8 = 0b11;
+
+/// `DUR[3:0]` の最大値
+pub const TAP_DURATION_MAX: u8 = 0b1111;
+
+/// 複数の軸で同時に閾値を超えたときに優先する軸の順 (`TAP_PRIORITY[2:0]`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TapPriority {
+    /// X > Y > Z
+    #[default]
+    Xyz,
+    /// Y > X > Z
+    Yxz,
+    /// X > Z > Y
+    Xzy,
+    /// Z > Y > X
+    Zyx,
+    /// Y > Z > X
+    Yzx,
+    /// Z > X > Y
+    Zxy,
+}
+
+impl TapPriority {
+    pub(super) const MASK: TapCfg1 = TapCfg1::TAP_PRIORITY_2
+        .union(TapCfg1::TAP_PRIORITY_1)
+        .union(TapCfg1::TAP_PRIORITY_0);
+
+    /// `TAP_CFG1` の `TAP_PRIORITY[2:0]` に設定する値
+    pub(super) fn bits(self) -> TapCfg1 {
+        let priority: u8 = match self {
+            TapPriority::Xyz => 0b000,
+            TapPriority::Yxz => 0b001,
+            TapPriority::Xzy => 0b010,
+            TapPriority::Zyx => 0b011,
+            TapPriority::Yzx => 0b101,
+            TapPriority::Zxy => 0b110,
+        };
+        TapCfg1::from_bits_retain(priority << 5)
+    }
+}
+
+/// 検出するタップ (`SINGLE_DOUBLE_TAP`)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TapMode {
+    /// Only single-tap event enabled
+    #[default]
+    SingleOnly,
+    /// Both single and double-tap events enabled
+    SingleAndDouble,
+}
+
+/// タップ検出の設定
+///
+/// 時間の設定は加速度計の ODR に対する倍数なので、ODR を変えると実際の長さも変わる
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[serde(default)]
+pub struct TapConfig {
+    /// X 軸の閾値 [LSB] (`None` なら X 軸では検出しない、最大 [`TAP_THRESHOLD_MAX`])
+    pub x_threshold: Option<u8>,
+    /// Y 軸の閾値 [LSB]
+    pub y_threshold: Option<u8>,
+    /// Z 軸の閾値 [LSB]
+    pub z_threshold: Option<u8>,
+    /// 閾値を超えている時間がこれより長ければタップとみなさない
+    /// (`SHOCK[1:0]`, 0 なら 4/ODR, それ以外は 8/ODR 単位)
+    pub shock: u8,
+    /// タップのあと、加速度が閾値を下回っているべき時間
+    /// (`QUIET[1:0]`, 0 なら 2/ODR, それ以外は 4/ODR 単位)
+    pub quiet: u8,
+    /// ダブルタップの1回目と2回目の最大の間隔
+    /// (`DUR[3:0]`, 0 なら 16/ODR, それ以外は 32/ODR 単位)
+    pub duration: u8,
+    pub mode: TapMode,
+    pub priority: TapPriority,
+}
+
+impl TapConfig {
+    /// `TAP_CFG0` のうち [`TapConfig`] で設定するビット
+    pub(super) const TAP_CFG0_MASK: TapCfg0 = TapCfg0::TAP_X_EN
+        .union(TapCfg0::TAP_Y_EN)
+        .union(TapCfg0::TAP_Z_EN);
+
+    /// `TAP_CFG1` の `TAP_THS_X[4:0]`
+    pub(super) const X_THRESHOLD_MASK: TapCfg1 = TapCfg1::TAP_THS_X_4
+        .union(TapCfg1::TAP_THS_X_3)
+        .union(TapCfg1::TAP_THS_X_2)
+        .union(TapCfg1::TAP_THS_X_1)
+        .union(TapCfg1::TAP_THS_X_0);
+
+    /// `TAP_CFG2` の `TAP_THS_Y[4:0]`
+    pub(super) const Y_THRESHOLD_MASK: TapCfg2 = TapCfg2::TAP_THS_Y_4
+        .union(TapCfg2::TAP_THS_Y_3)
+        .union(TapCfg2::TAP_THS_Y_2)
+        .union(TapCfg2::TAP_THS_Y_1)
+        .union(TapCfg2::TAP_THS_Y_0);
+
+    /// `TAP_THS_6D` の `TAP_THS_Z[4:0]`
+    pub(super) const Z_THRESHOLD_MASK: TapThs6d = TapThs6d::TAP_THS_Z_4
+        .union(TapThs6d::TAP_THS_Z_3)
+        .union(TapThs6d::TAP_THS_Z_2)
+        .union(TapThs6d::TAP_THS_Z_1)
+        .union(TapThs6d::TAP_THS_Z_0);
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn x_threshold(mut self, x_threshold: Option<u8>) -> Self {
+        self.x_threshold = x_threshold;
+        self
+    }
+
+    pub fn y_threshold(mut self, y_threshold: Option<u8>) -> Self {
+        self.y_threshold = y_threshold;
+        self
+    }
+
+    pub fn z_threshold(mut self, z_threshold: Option<u8>) -> Self {
+        self.z_threshold = z_threshold;
+        self
+    }
+
+    pub fn shock(mut self, shock: u8) -> Self {
+        self.shock = shock;
+        self
+    }
+
+    pub fn quiet(mut self, quiet: u8) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    pub fn duration(mut self, duration: u8) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn mode(mut self, mode: TapMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn priority(mut self, priority: TapPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// いずれかの軸で検出する
+    pub fn is_enabled(&self) -> bool {
+        self.x_threshold.is_some() || self.y_threshold.is_some() || self.z_threshold.is_some()
+    }
+
+    /// レジスタに書き込める値か
+    pub(super) fn is_valid(&self) -> bool {
+        let thresholds = [self.x_threshold, self.y_threshold, self.z_threshold];
+        thresholds
+            .iter()
+            .flatten()
+            .all(|&threshold| threshold <= TAP_THRESHOLD_MAX)
+            && self.shock <= TAP_SHOCK_MAX
+            && self.quiet <= TAP_QUIET_MAX
+            && self.duration <= TAP_DURATION_MAX
+    }
+
+    /// `TAP_CFG0` の `TAP_X_EN`, `TAP_Y_EN`, `TAP_Z_EN` に設定する値
+    pub(super) fn tap_cfg0(&self) -> TapCfg0 {
+        let mut reg = TapCfg0::empty();
+        reg.set(TapCfg0::TAP_X_EN, self.x_threshold.is_some());
+        reg.set(TapCfg0::TAP_Y_EN, self.y_threshold.is_some());
+        reg.set(TapCfg0::TAP_Z_EN, self.z_threshold.is_some());
+        reg
+    }
+
+    /// `TAP_CFG1` の `TAP_PRIORITY[2:0]`, `TAP_THS_X[4:0]` に設定する値
+    pub(super) fn tap_cfg1(&self) -> TapCfg1 {
+        self.priority.bits() | TapCfg1::from_bits_retain(self.x_threshold.unwrap_or(0))
+    }
+
+    /// `TAP_CFG2` の `TAP_THS_Y[4:0]` に設定する値
+    pub(super) fn tap_cfg2(&self) -> TapCfg2 {
+        TapCfg2::from_bits_retain(self.y_threshold.unwrap_or(0))
+    }
+
+    /// `TAP_THS_6D` の `TAP_THS_Z[4:0]` に設定する値
+    pub(super) fn tap_ths_6d(&self) -> TapThs6d {
+        TapThs6d::from_bits_retain(self.z_threshold.unwrap_or(0))
+    }
+
+    /// `INT_DUR2` に設定する値
+    pub(super) fn int_dur2(&self) -> IntDur2 {
+        IntDur2::from_bits_retain(self.duration << 4 | self.quiet << 2 | self.shock)
+    }
+}
+
+impl TapSrc {
+    /// 検出したタップ (`TAP_IA` が立っていなければ `None`)
+    ///
+    /// ダブルタップのときは `SINGLE_TAP` より `DOUBLE_TAP` を優先する
+    pub fn event(&self, timestamp: Option<u32>) -> Option<TapEvent> {
+        if !self.contains(TapSrc::TAP_IA) {
+            return None;
+        }
+        let kind = if self.contains(TapSrc::DOUBLE_TAP) {
+            TapKind::Double
+        } else if self.contains(TapSrc::SINGLE_TAP) {
+            TapKind::Single
+        } else {
+            return None;
+        };
+        let axis = if self.contains(TapSrc::X_TAP) {
+            TapAxis::X
+        } else if self.contains(TapSrc::Y_TAP) {
+            TapAxis::Y
+        } else if self.contains(TapSrc::Z_TAP) {
+            TapAxis::Z
+        } else {
+            return None;
+        };
+        let sign = if self.contains(TapSrc::TAP_SIGN) {
+            TapSign::Negative
+        } else {
+            TapSign::Positive
+        };
+        Some(TapEvent {
+            kind,
+            axis,
+            sign,
+            timestamp,
+        })
+    }
+}
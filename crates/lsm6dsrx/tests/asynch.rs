@@ -0,0 +1,217 @@
+#![cfg(feature = "async")]
+
+mod sim;
+
+use embassy_futures::block_on;
+use lsm6dsrx::{
+    AccelBatchRate, AccelFullScale, AccelOdr, AccelUserOffset, FifoConfig, FifoMode, FifoSample,
+    GyroBatchRate, InterruptConfig, InterruptRoute, Lsm6dsrxConfig, Lsm6dsrxError, Lsm6sdrx,
+    Lsm6sdrxAsync, RegisterAddress, SpiInterface, TapConfig, TapMode, TimestampBatch,
+};
+use schema::Calibration;
+
+use crate::sim::{FifoTag, SimDelay, SimError, Simulator};
+
+type Driver = Lsm6sdrxAsync<SpiInterface<Simulator>>;
+
+fn setup() -> (Simulator, Driver) {
+    let sim = Simulator::new();
+    let imu = block_on(Lsm6sdrxAsync::new(
+        SpiInterface::new(sim.clone()),
+        &mut SimDelay::default(),
+    ))
+    .expect("Failed to initialize.");
+    (sim, imu)
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "actual = {actual}, expected = {expected}"
+    );
+}
+
+#[test]
+fn with_config_runs_init_sequence_and_applies_config() {
+    let sim = Simulator::new();
+    let config = Lsm6dsrxConfig::new()
+        .accel_odr(AccelOdr::Hz104)
+        .accel_full_scale(AccelFullScale::G8);
+    let imu = block_on(Lsm6sdrxAsync::with_config(
+        SpiInterface::new(sim.clone()),
+        config.clone(),
+        &mut SimDelay::default(),
+    ))
+    .unwrap();
+
+    assert_eq!(imu.config(), &config);
+    // 最初にソフトウェアリセットする
+    assert_eq!(
+        sim.writes()[0],
+        (RegisterAddress::CTRL3_C.bits(), 0b0000_0101)
+    );
+    // BDU, IF_INC
+    assert_eq!(sim.register(RegisterAddress::CTRL3_C), 0b0100_0100);
+    // 104Hz, ±8g, LPF2
+    assert_eq!(sim.register(RegisterAddress::CTRL1_XL), 0b0100_1110);
+}
+
+#[test]
+fn with_config_reports_bus_error() {
+    let sim = Simulator::new();
+    sim.set_fault(true);
+
+    let result = block_on(Lsm6sdrxAsync::new(
+        SpiInterface::new(sim),
+        &mut SimDelay::default(),
+    ));
+    assert!(matches!(
+        result,
+        Err(Lsm6dsrxError::Bus(SimError::Injected))
+    ));
+}
+
+#[test]
+fn fetch_acceleration_converts_with_full_scale() {
+    let (sim, mut imu) = setup();
+    sim.set_acceleration_raw([16384, -16384, 1000]);
+
+    let acceleration = block_on(imu.fetch_acceleration()).unwrap();
+    assert_close(acceleration.x, 16384.0 * 0.061);
+    assert_close(acceleration.y, -16384.0 * 0.061);
+    assert_close(acceleration.z, 1000.0 * 0.061);
+    assert_eq!(acceleration.timestamp, None);
+}
+
+#[test]
+fn accel_calibration_is_applied_to_fetches() {
+    let (sim, mut imu) = setup();
+    imu.set_accel_calibration(Calibration {
+        matrix: [[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 1.0]],
+        bias: [1.0, 0.0, 0.0],
+    });
+
+    // 同期版と同じ値になる
+    sim.set_acceleration_raw([1000, 0, 0]);
+    let acceleration = block_on(imu.fetch_acceleration()).unwrap();
+    assert_close(acceleration.x, 2.0 * 60.0);
+    assert_close(acceleration.z, 60.0);
+
+    sim.push_fifo(FifoTag::AccelNc, [1000, 0, 0]);
+    let mut samples = Vec::new();
+    block_on(imu.drain_fifo_with(|sample| samples.push(sample))).unwrap();
+    let FifoSample::Acceleration(acceleration) = &samples[0] else {
+        panic!("unexpected sample: {:?}", samples[0]);
+    };
+    assert_close(acceleration.x, 2.0 * 60.0);
+}
+
+#[test]
+fn drain_fifo_decodes_tagged_words() {
+    let (sim, mut imu) = setup();
+    sim.push_fifo(FifoTag::AccelNc, [1000, 0, -1000]);
+    sim.push_fifo_timestamp(0x0002_0001);
+    sim.push_fifo(FifoTag::GyroNc, [10, 20, 30]);
+
+    let mut samples = Vec::new();
+    let count = block_on(imu.drain_fifo_with(|sample| samples.push(sample))).unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(sim.fifo_len(), 0);
+
+    let FifoSample::Acceleration(acceleration) = &samples[0] else {
+        panic!("unexpected sample: {:?}", samples[0]);
+    };
+    assert_close(acceleration.x, 1000.0 * 0.061);
+    assert_eq!(samples[1], FifoSample::Timestamp(0x0002_0001));
+    let FifoSample::AngularRate(angular_rate) = &samples[2] else {
+        panic!("unexpected sample: {:?}", samples[2]);
+    };
+    assert_close(angular_rate.y, 20.0 * 70.0);
+    assert_eq!(angular_rate.timestamp, Some(0x0002_0001));
+}
+
+#[test]
+fn drain_fifo_reports_overrun_after_reading() {
+    let (sim, mut imu) = setup();
+    sim.push_fifo(FifoTag::AccelNc, [1, 1, 1]);
+    sim.push_fifo(FifoTag::AccelNc, [2, 2, 2]);
+    sim.set_fifo_overrun(true);
+
+    let mut samples = Vec::new();
+    let result = block_on(imu.drain_fifo_with(|sample| samples.push(sample)));
+    assert!(matches!(result, Err(Lsm6dsrxError::FifoOverrun)));
+    assert_eq!(samples.len(), 2);
+    assert_eq!(sim.fifo_len(), 0);
+}
+
+#[test]
+fn timestamp_is_attached_when_enabled() {
+    let (sim, mut imu) = setup();
+    sim.set_timestamp(0x0001_0203);
+
+    block_on(imu.enable_timestamp(true)).unwrap();
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0b0010_0000);
+    assert_eq!(block_on(imu.fetch_timestamp()).unwrap(), 0x0001_0203);
+    assert_eq!(
+        block_on(imu.fetch_acceleration()).unwrap().timestamp,
+        Some(0x0001_0203)
+    );
+
+    block_on(imu.reset_timestamp()).unwrap();
+    assert_eq!(sim.timestamp(), 0);
+
+    // リセット後もタイムスタンプは有効なまま
+    block_on(imu.reset(&mut SimDelay::default())).unwrap();
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0b0010_0000);
+
+    block_on(imu.enable_timestamp(false)).unwrap();
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0);
+    assert_eq!(block_on(imu.fetch_acceleration()).unwrap().timestamp, None);
+}
+
+#[test]
+fn writes_same_registers_as_blocking_driver() {
+    let config = Lsm6dsrxConfig::new()
+        .accel_odr(AccelOdr::Hz104)
+        .accel_full_scale(AccelFullScale::G8)
+        .accel_user_offset(AccelUserOffset::new(1, -2, 3));
+    let fifo = FifoConfig::new()
+        .watermark(0x123)
+        .mode(FifoMode::Continuous)
+        .accel_batch_rate(AccelBatchRate::Hz104)
+        .gyro_batch_rate(GyroBatchRate::Hz104)
+        .timestamp_batch(TimestampBatch::Every1);
+    let tap = TapConfig::new()
+        .z_threshold(Some(0x09))
+        .mode(TapMode::SingleAndDouble);
+    let interrupts =
+        InterruptConfig::new().int1(InterruptRoute::new().fifo_watermark(true).single_tap(true));
+
+    let blocking = Simulator::new();
+    let mut imu = Lsm6sdrx::with_config(
+        SpiInterface::new(blocking.clone()),
+        config.clone(),
+        &mut SimDelay::default(),
+    )
+    .unwrap();
+    imu.configure_fifo(&fifo).unwrap();
+    imu.configure_tap(&tap).unwrap();
+    imu.configure_interrupts(&interrupts).unwrap();
+    imu.enable_timestamp(true).unwrap();
+    imu.reset(&mut SimDelay::default()).unwrap();
+
+    let asynch = Simulator::new();
+    let mut imu = block_on(Lsm6sdrxAsync::with_config(
+        SpiInterface::new(asynch.clone()),
+        config,
+        &mut SimDelay::default(),
+    ))
+    .unwrap();
+    block_on(imu.configure_fifo(&fifo)).unwrap();
+    block_on(imu.configure_tap(&tap)).unwrap();
+    block_on(imu.configure_interrupts(&interrupts)).unwrap();
+    block_on(imu.enable_timestamp(true)).unwrap();
+    block_on(imu.reset(&mut SimDelay::default())).unwrap();
+
+    assert_eq!(asynch.writes(), blocking.writes());
+}
@@ -0,0 +1,123 @@
+use lsm6dsrx::{GyroBiasConfig, GyroBiasEstimator};
+use schema::{Acceleration, AngularRate};
+
+fn acceleration(x: f64, y: f64, z: f64) -> Acceleration {
+    Acceleration {
+        x,
+        y,
+        z,
+        timestamp: None,
+    }
+}
+
+fn angular_rate(x: f64, y: f64, z: f64) -> AngularRate {
+    AngularRate {
+        x,
+        y,
+        z,
+        timestamp: None,
+    }
+}
+
+/// 静止した状態のサンプルを `count` 組取り込む (小さなノイズを加える)
+fn feed_still(estimator: &mut GyroBiasEstimator, bias: [f64; 3], count: usize) {
+    for i in 0..count {
+        let noise = if i % 2 == 0 { 1.0 } else { -1.0 };
+        estimator.update(
+            &acceleration(noise, -noise, 1000.0 + noise),
+            &angular_rate(bias[0] + 10.0 * noise, bias[1], bias[2] - 10.0 * noise),
+        );
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "actual = {actual}, expected = {expected}"
+    );
+}
+
+#[test]
+fn bias_starts_at_zero_without_confidence() {
+    let estimator = GyroBiasEstimator::default();
+    let bias = estimator.bias();
+    assert_eq!((bias.x, bias.y, bias.z), (0.0, 0.0, 0.0));
+    assert_eq!(bias.confidence, 0.0);
+}
+
+#[test]
+fn still_window_sets_bias() {
+    let mut estimator = GyroBiasEstimator::default();
+    let window = estimator.config().window as usize;
+
+    feed_still(&mut estimator, [300.0, -200.0, 50.0], window - 2);
+    // 区間が終わるまでは更新しない
+    assert_eq!(estimator.bias().confidence, 0.0);
+
+    feed_still(&mut estimator, [300.0, -200.0, 50.0], 2);
+    let bias = estimator.bias();
+    assert_close(bias.x, 300.0);
+    assert_close(bias.y, -200.0);
+    assert_close(bias.z, 50.0);
+    assert_close(bias.confidence, 0.1);
+
+    let compensated = estimator.compensate(angular_rate(300.0, 0.0, 0.0));
+    assert_close(compensated.x, 0.0);
+    assert_close(compensated.y, 200.0);
+    assert_close(compensated.z, -50.0);
+}
+
+#[test]
+fn later_windows_are_smoothed() {
+    let mut estimator = GyroBiasEstimator::new(GyroBiasConfig {
+        window: 10,
+        smoothing: 0.5,
+        confident_windows: 2,
+        ..GyroBiasConfig::default()
+    });
+
+    feed_still(&mut estimator, [100.0, 0.0, 0.0], 10);
+    feed_still(&mut estimator, [200.0, 0.0, 0.0], 10);
+    assert_close(estimator.bias().x, 150.0);
+    assert_close(estimator.bias().confidence, 1.0);
+
+    // 信頼度は 1 を超えない
+    feed_still(&mut estimator, [200.0, 0.0, 0.0], 10);
+    assert_close(estimator.bias().x, 175.0);
+    assert_close(estimator.bias().confidence, 1.0);
+
+    estimator.reset();
+    assert_eq!(estimator.bias().confidence, 0.0);
+    assert_eq!(estimator.config().window, 10);
+}
+
+#[test]
+fn rotation_is_not_taken_as_bias() {
+    let mut estimator = GyroBiasEstimator::default();
+    let window = estimator.config().window;
+
+    for i in 0..window {
+        let rate = i as f64 * 1000.0;
+        assert!(!estimator.update(
+            &acceleration(0.0, 0.0, 1000.0),
+            &angular_rate(rate, 0.0, 0.0)
+        ));
+    }
+    assert_eq!(estimator.bias().confidence, 0.0);
+    assert_eq!(estimator.bias().x, 0.0);
+}
+
+#[test]
+fn shaking_is_not_taken_as_bias() {
+    let mut estimator = GyroBiasEstimator::default();
+    let window = estimator.config().window;
+
+    for i in 0..window {
+        let shake = if i % 2 == 0 { 100.0 } else { -100.0 };
+        estimator.update(
+            &acceleration(shake, 0.0, 1000.0),
+            &angular_rate(300.0, 0.0, 0.0),
+        );
+    }
+    assert_eq!(estimator.bias().confidence, 0.0);
+}
@@ -0,0 +1,117 @@
+use lsm6dsrx::{Orientation, SixPositionCalibration};
+use schema::{Acceleration, Calibration};
+
+/// 感度のずれと軸間の傾き
+const SENSITIVITY: [[f64; 3]; 3] = [
+    [1.02, 0.01, -0.02],
+    [0.015, 0.97, 0.005],
+    [-0.01, 0.02, 1.05],
+];
+
+/// ゼロ点のずれ [mg]
+const BIAS: [f64; 3] = [25.0, -40.0, 12.0];
+
+/// 真の加速度に対するセンサの出力
+fn measure(actual: [f64; 3]) -> [f64; 3] {
+    let mut measured = BIAS;
+    for (i, value) in measured.iter_mut().enumerate() {
+        for (j, actual) in actual.iter().enumerate() {
+            *value += SENSITIVITY[i][j] * actual;
+        }
+    }
+    measured
+}
+
+fn acceleration([x, y, z]: [f64; 3]) -> Acceleration {
+    Acceleration {
+        x,
+        y,
+        z,
+        timestamp: None,
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-6,
+        "actual = {actual}, expected = {expected}"
+    );
+}
+
+fn synthetic_calibration() -> SixPositionCalibration {
+    let mut calibration = SixPositionCalibration::new();
+    for orientation in Orientation::ALL {
+        calibration.record(orientation, measure(orientation.gravity()));
+    }
+    calibration
+}
+
+#[test]
+fn default_calibration_does_nothing() {
+    let acceleration = Calibration::default().apply(Acceleration {
+        x: 1.0,
+        y: -2.0,
+        z: 3.0,
+        timestamp: Some(7),
+    });
+    assert_eq!(
+        acceleration,
+        Acceleration {
+            x: 1.0,
+            y: -2.0,
+            z: 3.0,
+            timestamp: Some(7),
+        }
+    );
+}
+
+#[test]
+fn solve_requires_all_orientations() {
+    let mut calibration = SixPositionCalibration::new();
+    for orientation in &Orientation::ALL[..5] {
+        calibration.record(*orientation, measure(orientation.gravity()));
+    }
+    assert!(!calibration.is_complete());
+    assert_eq!(
+        calibration.missing().collect::<Vec<_>>(),
+        vec![Orientation::ZDown]
+    );
+    assert_eq!(calibration.solve(), None);
+}
+
+#[test]
+fn solve_recovers_bias_and_matrix() {
+    let calibration = synthetic_calibration();
+    assert!(calibration.is_complete());
+
+    let solved = calibration.solve().unwrap();
+    for (solved, expected) in solved.bias.iter().zip(BIAS) {
+        assert_close(*solved, expected);
+    }
+
+    // 各姿勢の測定値が理想的な重力に戻る
+    for orientation in Orientation::ALL {
+        let corrected = solved.apply(acceleration(measure(orientation.gravity())));
+        let [x, y, z] = orientation.gravity();
+        assert_close(corrected.x, x);
+        assert_close(corrected.y, y);
+        assert_close(corrected.z, z);
+    }
+
+    // 6つの姿勢以外の向きでも補正できる
+    let actual = [300.0, -500.0, 812.0];
+    let corrected = solved.apply(acceleration(measure(actual)));
+    assert_close(corrected.x, actual[0]);
+    assert_close(corrected.y, actual[1]);
+    assert_close(corrected.z, actual[2]);
+}
+
+#[test]
+fn solve_rejects_degenerate_measurements() {
+    let mut calibration = SixPositionCalibration::new();
+    // 向きを変えずに記録した
+    for orientation in Orientation::ALL {
+        calibration.record(orientation, [0.0, 0.0, 1000.0]);
+    }
+    assert_eq!(calibration.solve(), None);
+}
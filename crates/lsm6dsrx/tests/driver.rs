@@ -0,0 +1,974 @@
+mod sim;
+
+use lsm6dsrx::{
+    AccelBatchRate, AccelFullScale, AccelOdr, AccelUserOffset, Ctrl10C, Ctrl1Xl, Ctrl3C,
+    FifoConfig, FifoMode, FifoSample, GyroBatchRate, GyroFullScale, InterruptConfig,
+    InterruptPinMode, InterruptPolarity, InterruptRoute, InterruptSignal, Lsm6dsrxConfig,
+    Lsm6dsrxError, Lsm6sdrx, OffsetWeight, RegisterAddress, RegisterValue, SpiInterface, StatusReg,
+    TapConfig, TapMode, TapPriority, TapSrc, FIFO_WATERMARK_MAX, TAP_THRESHOLD_MAX,
+};
+
+use schema::{Calibration, TapAxis, TapEvent, TapKind, TapSign};
+
+use crate::sim::{FifoTag, SimDelay, SimError, Simulator};
+
+type Driver = Lsm6sdrx<SpiInterface<Simulator>>;
+
+fn setup() -> (Simulator, Driver) {
+    let sim = Simulator::new();
+    let imu = Lsm6sdrx::new(SpiInterface::new(sim.clone()), &mut SimDelay::default())
+        .expect("Failed to initialize.");
+    (sim, imu)
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "actual = {actual}, expected = {expected}"
+    );
+}
+
+#[test]
+fn new_rejects_unknown_who_am_i() {
+    let sim = Simulator::new();
+    sim.set_register(RegisterAddress::WHO_AM_I, 0x6A);
+
+    let result = Lsm6sdrx::new(SpiInterface::new(sim.clone()), &mut SimDelay::default());
+    assert!(matches!(result, Err(Lsm6dsrxError::WhoAmI(0x6A))));
+    // WHO_AM_I を読んだだけで何も書き込まない
+    assert!(sim.writes().is_empty());
+}
+
+#[test]
+fn new_reports_bus_error() {
+    let sim = Simulator::new();
+    sim.set_fault(true);
+
+    let result = Lsm6sdrx::new(SpiInterface::new(sim), &mut SimDelay::default());
+    assert!(matches!(
+        result,
+        Err(Lsm6dsrxError::Bus(SimError::Injected))
+    ));
+}
+
+#[test]
+fn new_runs_init_sequence() {
+    let (sim, _imu) = setup();
+
+    let writes = sim.writes();
+    // 最初にソフトウェアリセットする
+    assert_eq!(writes[0], (RegisterAddress::CTRL3_C.bits(), 0b0000_0101));
+
+    // BDU, IF_INC
+    assert_eq!(sim.register(RegisterAddress::CTRL3_C), 0b0100_0100);
+    // I3C_DISABLE
+    assert_eq!(sim.register(RegisterAddress::CTRL9_XL), 0b1110_0010);
+    // USR_OFF_ON_OUT
+    assert_eq!(sim.register(RegisterAddress::CTRL7_G), 0b0000_0010);
+}
+
+#[test]
+fn new_applies_default_config() {
+    let (sim, imu) = setup();
+
+    assert_eq!(imu.config(), &Lsm6dsrxConfig::default());
+    // 1.66kHz, ±2g, LPF2
+    assert_eq!(sim.register(RegisterAddress::CTRL1_XL), 0b1000_0010);
+    // 1.66kHz, ±2000dps
+    assert_eq!(sim.register(RegisterAddress::CTRL2_G), 0b1000_1100);
+    // LPF1_SEL_G
+    assert_eq!(sim.register(RegisterAddress::CTRL4_C), 0b0000_0010);
+    // FTYPE = 010
+    assert_eq!(sim.register(RegisterAddress::CTRL6_C), 0b0000_0010);
+    // HPCF_XL = 001
+    assert_eq!(sim.register(RegisterAddress::CTRL8_XL), 0b0010_0000);
+}
+
+#[test]
+fn fetch_acceleration_converts_with_full_scale() {
+    let (sim, mut imu) = setup();
+    sim.set_acceleration_raw([16384, -16384, 1000]);
+
+    let acceleration = imu.fetch_acceleration().unwrap();
+    assert_close(acceleration.x, 16384.0 * 0.061);
+    assert_close(acceleration.y, -16384.0 * 0.061);
+    assert_close(acceleration.z, 1000.0 * 0.061);
+    assert_eq!(acceleration.timestamp, None);
+
+    imu.set_accel_full_scale(AccelFullScale::G16).unwrap();
+    assert_eq!(
+        sim.register(RegisterAddress::CTRL1_XL) & 0b0000_1100,
+        0b0000_0100
+    );
+    let acceleration = imu.fetch_acceleration().unwrap();
+    assert_close(acceleration.x, 16384.0 * 0.488);
+}
+
+#[test]
+fn fetch_angular_rate_converts_with_full_scale() {
+    let (sim, mut imu) = setup();
+    sim.set_angular_rate_raw([100, -200, 300]);
+
+    let angular_rate = imu.fetch_angular_rate().unwrap();
+    assert_close(angular_rate.x, 100.0 * 70.0);
+    assert_close(angular_rate.y, -200.0 * 70.0);
+    assert_close(angular_rate.z, 300.0 * 70.0);
+}
+
+#[test]
+fn fetch_temperature_converts_to_celsius() {
+    let (sim, mut imu) = setup();
+
+    sim.set_temperature_raw(0);
+    assert_close(imu.fetch_temperature().unwrap().celsius, 25.0);
+
+    sim.set_temperature_raw(256);
+    assert_close(imu.fetch_temperature().unwrap().celsius, 26.0);
+
+    sim.set_temperature_raw(-512);
+    assert_close(imu.fetch_temperature().unwrap().celsius, 23.0);
+}
+
+#[test]
+fn fetch_motion_reads_all_outputs() {
+    let (sim, mut imu) = setup();
+    sim.set_temperature_raw(128);
+    sim.set_angular_rate_raw([1, 2, 3]);
+    sim.set_acceleration_raw([4, 5, 6]);
+
+    let motion = imu.fetch_motion().unwrap();
+    assert_close(motion.temperature, 25.5);
+    assert_close(motion.angular_rate.x, 70.0);
+    assert_close(motion.angular_rate.z, 210.0);
+    assert_close(motion.acceleration.x, 4.0 * 0.061);
+    assert_close(motion.acceleration.z, 6.0 * 0.061);
+}
+
+#[test]
+fn fetch_motion_estimates_and_subtracts_gyro_bias() {
+    let (sim, mut imu) = setup();
+    // 静止して Z 軸が上を向いている
+    sim.set_acceleration_raw([0, 0, 16393]);
+    sim.set_angular_rate_raw([10, -5, 2]);
+
+    let window = imu.gyro_bias_estimator_mut().config().window;
+    for _ in 0..window {
+        imu.fetch_motion().unwrap();
+    }
+
+    let bias = imu.gyro_bias();
+    assert_close(bias.x, 700.0);
+    assert_close(bias.y, -350.0);
+    assert_close(bias.z, 140.0);
+    assert!(bias.confidence > 0.0);
+
+    let motion = imu.fetch_motion().unwrap();
+    assert_close(motion.angular_rate.x, 0.0);
+    assert_close(motion.angular_rate.z, 0.0);
+
+    sim.set_angular_rate_raw([20, -5, 2]);
+    let angular_rate = imu.fetch_angular_rate().unwrap();
+    assert_close(angular_rate.x, 700.0);
+    assert_close(angular_rate.y, 0.0);
+
+    imu.gyro_bias_estimator_mut().reset();
+    assert_close(imu.fetch_angular_rate().unwrap().x, 1400.0);
+}
+
+#[test]
+fn drain_fifo_estimates_gyro_bias_from_paired_samples() {
+    let (sim, mut imu) = setup();
+    let window = imu.gyro_bias_estimator_mut().config().window;
+    for _ in 0..window {
+        // 静止して Z 軸が上を向いている
+        sim.push_fifo(FifoTag::AccelNc, [0, 0, 16393]);
+        sim.push_fifo(FifoTag::GyroNc, [10, -5, 2]);
+    }
+
+    let mut samples = Vec::new();
+    imu.drain_fifo_with(|sample| samples.push(sample)).unwrap();
+
+    let bias = imu.gyro_bias();
+    assert_close(bias.x, 700.0);
+    assert_close(bias.y, -350.0);
+    assert_close(bias.z, 140.0);
+    // 推定したバイアスは最後の角速度から引かれている
+    let Some(FifoSample::AngularRate(angular_rate)) = samples.last() else {
+        panic!("unexpected sample: {:?}", samples.last());
+    };
+    assert_close(angular_rate.x, 0.0);
+}
+
+#[test]
+fn accel_calibration_is_applied_to_fetches() {
+    let (sim, mut imu) = setup();
+    sim.set_acceleration_raw([1000, 0, 0]);
+    imu.set_accel_calibration(Calibration {
+        matrix: [[2.0, 0.0, 0.0], [0.0, 1.0, 0.0], [1.0, 0.0, 1.0]],
+        bias: [1.0, 0.0, 0.0],
+    });
+
+    let acceleration = imu.fetch_acceleration().unwrap();
+    assert_close(acceleration.x, 2.0 * 60.0);
+    assert_close(acceleration.z, 60.0);
+    let motion = imu.fetch_motion().unwrap();
+    assert_close(motion.acceleration.x, 2.0 * 60.0);
+
+    // 平均は補正をかけない値
+    let mean = imu
+        .average_acceleration(4, &mut SimDelay::default())
+        .unwrap();
+    assert_close(mean[0], 61.0);
+}
+
+#[test]
+fn status_reports_new_data() {
+    let (sim, mut imu) = setup();
+    assert_eq!(imu.status().unwrap(), StatusReg::empty());
+
+    sim.set_acceleration_raw([1, 2, 3]);
+    sim.set_angular_rate_raw([4, 5, 6]);
+    assert_eq!(imu.status().unwrap(), StatusReg::XLDA | StatusReg::GDA);
+
+    imu.fetch_acceleration().unwrap();
+    assert_eq!(imu.status().unwrap(), StatusReg::GDA);
+}
+
+#[test]
+fn try_fetch_returns_only_fresh_samples() {
+    let (sim, mut imu) = setup();
+    assert_eq!(imu.try_fetch_acceleration().unwrap(), None);
+
+    sim.set_acceleration_raw([1000, 0, 0]);
+    let acceleration = imu.try_fetch_acceleration().unwrap().unwrap();
+    assert_close(acceleration.x, 1000.0 * 0.061);
+    // 同じ変換結果は2回返さない
+    assert_eq!(imu.try_fetch_acceleration().unwrap(), None);
+
+    sim.set_angular_rate_raw([1, 0, 0]);
+    assert!(imu.try_fetch_angular_rate().unwrap().is_some());
+    assert!(imu.try_fetch_angular_rate().unwrap().is_none());
+}
+
+#[test]
+fn try_fetch_motion_waits_for_both_sensors() {
+    let (sim, mut imu) = setup();
+    sim.set_acceleration_raw([1, 2, 3]);
+    assert!(imu.try_fetch_motion().unwrap().is_none());
+
+    sim.set_angular_rate_raw([4, 5, 6]);
+    let motion = imu.try_fetch_motion().unwrap().unwrap();
+    assert_close(motion.acceleration.x, 0.061);
+    assert_close(motion.angular_rate.x, 4.0 * 70.0);
+    assert!(imu.try_fetch_motion().unwrap().is_none());
+}
+
+#[test]
+fn wait_for_accel_ready_returns_when_data_is_available() {
+    let (sim, mut imu) = setup();
+    sim.set_acceleration_raw([1, 2, 3]);
+
+    let mut delay = SimDelay::default();
+    imu.wait_for_accel_ready(1_000, &mut delay).unwrap();
+    assert_eq!(delay.total_ns, 0);
+}
+
+#[test]
+fn wait_for_data_ready_times_out() {
+    let (_sim, mut imu) = setup();
+
+    let mut delay = SimDelay::default();
+    let result = imu.wait_for_gyro_ready(1_000, &mut delay);
+    assert!(matches!(result, Err(Lsm6dsrxError::DataReadyTimeout)));
+    assert_eq!(delay.total_ns, 1_000_000);
+}
+
+#[test]
+fn dump_registers_decodes_known_registers() {
+    let (sim, mut imu) = setup();
+    sim.push_fifo(FifoTag::AccelNc, [1, 2, 3]);
+
+    let mut dump = Vec::new();
+    imu.dump_registers_with(|reg| dump.push(reg)).unwrap();
+    let ctrl3_c = dump.iter().find(|reg| reg.name == "CTRL3_C").unwrap();
+    assert_eq!(ctrl3_c.address, 0x12);
+    assert_eq!(ctrl3_c.raw, 0b0100_0100);
+    assert_eq!(
+        ctrl3_c.value,
+        RegisterValue::Ctrl3C(Ctrl3C::BDU | Ctrl3C::IF_INC)
+    );
+
+    let who_am_i = dump.iter().find(|reg| reg.name == "WHO_AM_I").unwrap();
+    assert_eq!(who_am_i.value, RegisterValue::Raw(0x6B));
+
+    // FIFO の出力は読まない
+    assert!(dump
+        .iter()
+        .all(|reg| !reg.name.starts_with("FIFO_DATA_OUT")));
+    assert_eq!(sim.fifo_len(), 1);
+
+    // ラッチした割り込みを消さないように、要因のレジスタも読まない
+    assert!(dump.iter().all(|reg| !reg.name.ends_with("_SRC")));
+}
+
+#[test]
+fn modify_reads_changes_and_writes() {
+    let (sim, mut imu) = setup();
+    sim.clear_writes();
+
+    imu.modify::<Ctrl1Xl>(|reg| reg.set_odr(AccelOdr::Hz52))
+        .unwrap();
+    assert_eq!(
+        sim.writes(),
+        vec![(RegisterAddress::CTRL1_XL.bits(), 0b0011_0010)]
+    );
+    assert_eq!(
+        imu.read_register::<Ctrl1Xl>().unwrap().odr(),
+        Some(AccelOdr::Hz52)
+    );
+}
+
+#[test]
+fn modify_verifies_written_value() {
+    let (sim, mut imu) = setup();
+    sim.set_stuck_bits(RegisterAddress::CTRL10_C, Ctrl10C::TIMESTAMP_EN.bits());
+
+    // 確認しなければ気づかない
+    imu.enable_timestamp(true).unwrap();
+
+    imu.set_verify_writes(true);
+    let result = imu.enable_timestamp(true);
+    assert!(matches!(
+        result,
+        Err(Lsm6dsrxError::VerifyFailed {
+            address: 0x19,
+            expected: 0b0010_0000,
+            actual: 0,
+        })
+    ));
+}
+
+#[test]
+fn verified_reset_succeeds() {
+    let (_sim, mut imu) = setup();
+    imu.set_verify_writes(true);
+
+    imu.reset(&mut SimDelay::default()).unwrap();
+}
+
+#[test]
+fn timestamp_is_attached_when_enabled() {
+    let (sim, mut imu) = setup();
+    sim.set_timestamp(0x0001_0203);
+
+    imu.enable_timestamp(true).unwrap();
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0b0010_0000);
+    assert_eq!(imu.fetch_timestamp().unwrap(), 0x0001_0203);
+    assert_eq!(
+        imu.fetch_acceleration().unwrap().timestamp,
+        Some(0x0001_0203)
+    );
+    assert_eq!(
+        imu.fetch_angular_rate().unwrap().timestamp,
+        Some(0x0001_0203)
+    );
+
+    imu.reset_timestamp().unwrap();
+    assert_eq!(sim.timestamp(), 0);
+
+    imu.enable_timestamp(false).unwrap();
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0);
+    assert_eq!(imu.fetch_acceleration().unwrap().timestamp, None);
+}
+
+#[test]
+fn config_enables_timestamp() {
+    let sim = Simulator::new();
+    sim.set_timestamp(0x0001_0203);
+    let config = Lsm6dsrxConfig::new().timestamp(true);
+    let mut imu = Lsm6sdrx::with_config(
+        SpiInterface::new(sim.clone()),
+        config,
+        &mut SimDelay::default(),
+    )
+    .unwrap();
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0b0010_0000);
+    assert_eq!(
+        imu.fetch_acceleration().unwrap().timestamp,
+        Some(0x0001_0203)
+    );
+
+    imu.enable_timestamp(false).unwrap();
+    assert!(!imu.config().timestamp);
+    imu.apply_config(&Lsm6dsrxConfig::new().timestamp(true))
+        .unwrap();
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0b0010_0000);
+}
+
+#[test]
+fn configure_fifo_writes_registers() {
+    let (sim, mut imu) = setup();
+
+    let config = FifoConfig::new()
+        .watermark(0x123)
+        .stop_on_watermark(true)
+        .mode(FifoMode::Continuous)
+        .accel_batch_rate(AccelBatchRate::Hz104)
+        .gyro_batch_rate(GyroBatchRate::Hz104);
+    imu.configure_fifo(&config).unwrap();
+
+    assert_eq!(sim.register(RegisterAddress::FIFO_CTRL1), 0x23);
+    // STOP_ON_WTM, WTM8
+    assert_eq!(sim.register(RegisterAddress::FIFO_CTRL2), 0b1000_0001);
+    assert_eq!(sim.register(RegisterAddress::FIFO_CTRL3), 0b0100_0100);
+    assert_eq!(sim.register(RegisterAddress::FIFO_CTRL4), 0b0000_0110);
+}
+
+#[test]
+fn configure_fifo_rejects_large_watermark() {
+    let (sim, mut imu) = setup();
+    sim.clear_writes();
+
+    let config = FifoConfig::new().watermark(FIFO_WATERMARK_MAX + 1);
+    assert!(matches!(
+        imu.configure_fifo(&config),
+        Err(Lsm6dsrxError::InvalidConfig)
+    ));
+    assert!(sim.writes().is_empty());
+}
+
+#[test]
+fn fifo_status_reports_unread_words() {
+    let (sim, mut imu) = setup();
+    imu.configure_fifo(&FifoConfig::new().watermark(2)).unwrap();
+    sim.push_fifo(FifoTag::AccelNc, [0, 0, 0]);
+
+    let status = imu.fifo_status().unwrap();
+    assert_eq!(status.unread, 1);
+    assert!(!status.watermark);
+    assert!(!status.overrun);
+
+    sim.push_fifo(FifoTag::AccelNc, [0, 0, 0]);
+    sim.set_fifo_overrun(true);
+    let status = imu.fifo_status().unwrap();
+    assert_eq!(status.unread, 2);
+    assert!(status.watermark);
+    assert!(status.overrun);
+}
+
+#[test]
+fn drain_fifo_decodes_tagged_words() {
+    let (sim, mut imu) = setup();
+    sim.push_fifo(FifoTag::AccelNc, [1000, 0, -1000]);
+    sim.push_fifo_timestamp(0x0002_0001);
+    sim.push_fifo(FifoTag::GyroNc, [10, 20, 30]);
+    sim.push_fifo(FifoTag::Temperature, [256, 0, 0]);
+
+    let mut samples = Vec::new();
+    let count = imu.drain_fifo_with(|sample| samples.push(sample)).unwrap();
+    assert_eq!(count, 4);
+    assert_eq!(sim.fifo_len(), 0);
+    assert_eq!(samples.len(), 4);
+
+    let FifoSample::Acceleration(acceleration) = &samples[0] else {
+        panic!("unexpected sample: {:?}", samples[0]);
+    };
+    assert_close(acceleration.x, 1000.0 * 0.061);
+    assert_close(acceleration.z, -1000.0 * 0.061);
+    // タイムスタンプより前のデータには付かない
+    assert_eq!(acceleration.timestamp, None);
+
+    assert_eq!(samples[1], FifoSample::Timestamp(0x0002_0001));
+
+    let FifoSample::AngularRate(angular_rate) = &samples[2] else {
+        panic!("unexpected sample: {:?}", samples[2]);
+    };
+    assert_close(angular_rate.y, 20.0 * 70.0);
+    assert_eq!(angular_rate.timestamp, Some(0x0002_0001));
+
+    let FifoSample::Temperature(celsius) = samples[3] else {
+        panic!("unexpected sample: {:?}", samples[3]);
+    };
+    assert_close(celsius, 26.0);
+}
+
+#[test]
+fn drain_fifo_uses_configured_full_scale() {
+    let (sim, mut imu) = setup();
+    imu.apply_config(&Lsm6dsrxConfig::new().gyro_full_scale(GyroFullScale::Dps125))
+        .unwrap();
+    sim.push_fifo(FifoTag::GyroNc, [8, 0, 0]);
+
+    let mut samples = Vec::new();
+    imu.drain_fifo_with(|sample| samples.push(sample)).unwrap();
+
+    let FifoSample::AngularRate(angular_rate) = &samples[0] else {
+        panic!("unexpected sample: {:?}", samples[0]);
+    };
+    assert_close(angular_rate.x, 8.0 * 4.375);
+}
+
+#[test]
+fn drain_fifo_skips_unknown_tags() {
+    let (sim, mut imu) = setup();
+    sim.push_fifo(FifoTag::AccelNc, [1, 1, 1]);
+    sim.push_fifo_raw([0x1F << 3, 0, 0, 0, 0, 0, 0]);
+    sim.push_fifo(FifoTag::AccelNc, [2, 2, 2]);
+
+    let mut samples = Vec::new();
+    let count = imu.drain_fifo_with(|sample| samples.push(sample)).unwrap();
+    assert_eq!(count, 3);
+    assert_eq!(samples.len(), 2);
+}
+
+#[test]
+fn configure_interrupts_routes_events_to_pins() {
+    let (sim, mut imu) = setup();
+    let config = InterruptConfig::new()
+        .int1(
+            InterruptRoute::new()
+                .accel_data_ready(true)
+                .fifo_watermark(true),
+        )
+        .int2(
+            InterruptRoute::new()
+                .temperature_data_ready(true)
+                .single_tap(true)
+                .double_tap(true),
+        )
+        .polarity(InterruptPolarity::ActiveLow)
+        .event_signal(InterruptSignal::Latched)
+        .data_ready_signal(InterruptSignal::Pulsed);
+    imu.configure_interrupts(&config).unwrap();
+
+    assert_eq!(sim.register(RegisterAddress::INT1_CTRL), 0b0000_1001);
+    assert_eq!(sim.register(RegisterAddress::MD1_CFG), 0);
+    assert_eq!(sim.register(RegisterAddress::INT2_CTRL), 0b0000_0100);
+    assert_eq!(sim.register(RegisterAddress::MD2_CFG), 0b0100_1000);
+    // H_LACTIVE が立ち、BDU と IF_INC は残る
+    assert_eq!(sim.register(RegisterAddress::CTRL3_C), 0b0110_0100);
+    assert_eq!(sim.register(RegisterAddress::TAP_CFG0) & 0b0000_0001, 1);
+    assert_eq!(
+        sim.register(RegisterAddress::COUNTER_BDR_REG1) & 0b1000_0000,
+        0b1000_0000
+    );
+    assert_eq!(
+        sim.register(RegisterAddress::TAP_CFG2) & 0b1000_0000,
+        0b1000_0000
+    );
+
+    // 割り当てを外しても、タップなどの検出は止めない
+    imu.configure_interrupts(&InterruptConfig::new()).unwrap();
+    assert_eq!(sim.register(RegisterAddress::INT1_CTRL), 0);
+    assert_eq!(sim.register(RegisterAddress::MD2_CFG), 0);
+    assert_eq!(
+        sim.register(RegisterAddress::TAP_CFG2) & 0b1000_0000,
+        0b1000_0000
+    );
+    assert_eq!(sim.register(RegisterAddress::CTRL3_C), 0b0100_0100);
+}
+
+#[test]
+fn configure_interrupts_rejects_invalid_config() {
+    let (sim, mut imu) = setup();
+    sim.clear_writes();
+
+    let temperature_on_int1 =
+        InterruptConfig::new().int1(InterruptRoute::new().temperature_data_ready(true));
+    let result = imu.configure_interrupts(&temperature_on_int1);
+    assert!(matches!(result, Err(Lsm6dsrxError::InvalidConfig)));
+
+    let active_low_open_drain = InterruptConfig::new()
+        .polarity(InterruptPolarity::ActiveLow)
+        .pin_mode(InterruptPinMode::OpenDrain);
+    let result = imu.configure_interrupts(&active_low_open_drain);
+    assert!(matches!(result, Err(Lsm6dsrxError::InvalidConfig)));
+
+    assert!(sim.writes().is_empty());
+}
+
+#[test]
+fn interrupt_sources_decodes_source_registers() {
+    let (sim, mut imu) = setup();
+    assert!(!imu.interrupt_sources().unwrap().any());
+
+    sim.set_register(RegisterAddress::ALL_INT_SRC, 0b0000_0100);
+    sim.set_register(RegisterAddress::TAP_SRC, 0b0110_1010);
+    sim.set_register(RegisterAddress::EMB_FUNC_STATUS_MAINPAGE, 0b0000_1000);
+    sim.set_acceleration_raw([1, 2, 3]);
+    sim.push_fifo(FifoTag::AccelNc, [1, 2, 3]);
+    sim.set_fifo_overrun(true);
+
+    let sources = imu.interrupt_sources().unwrap();
+    assert!(sources.any());
+    assert!(sources.single_tap);
+    assert!(!sources.double_tap);
+    assert_eq!(
+        sources.tap(),
+        Some(TapEvent {
+            kind: TapKind::Single,
+            axis: TapAxis::Y,
+            sign: TapSign::Negative,
+            timestamp: None,
+        })
+    );
+    assert_eq!(
+        sources.tap_source,
+        TapSrc::TAP_IA | TapSrc::SINGLE_TAP | TapSrc::TAP_SIGN | TapSrc::Y_TAP
+    );
+    assert!(sources.accel_data_ready);
+    assert!(!sources.gyro_data_ready);
+    assert!(sources.fifo_overrun);
+    assert!(!sources.fifo_watermark);
+    assert!(sources.embedded_function);
+    assert!(!sources.wake_up);
+}
+
+#[test]
+fn configure_tap_writes_thresholds_and_windows() {
+    let (sim, mut imu) = setup();
+    let config = TapConfig::new()
+        .x_threshold(Some(0x09))
+        .z_threshold(Some(TAP_THRESHOLD_MAX))
+        .shock(2)
+        .quiet(1)
+        .duration(7)
+        .mode(TapMode::SingleAndDouble)
+        .priority(TapPriority::Zyx);
+    imu.configure_tap(&config).unwrap();
+
+    // X と Z だけ有効
+    assert_eq!(
+        sim.register(RegisterAddress::TAP_CFG0) & 0b0000_1110,
+        0b0000_1010
+    );
+    assert_eq!(sim.register(RegisterAddress::TAP_CFG1), 0b0110_1001);
+    assert_eq!(sim.register(RegisterAddress::TAP_CFG2), 0b1000_0000);
+    assert_eq!(sim.register(RegisterAddress::TAP_THS_6D), 0b0001_1111);
+    assert_eq!(sim.register(RegisterAddress::INT_DUR2), 0b0111_0110);
+    assert_eq!(sim.register(RegisterAddress::WAKE_UP_THS), 0b1000_0000);
+
+    // 無効にしても、ほかの機能が使う INTERRUPTS_ENABLE は残す
+    imu.configure_tap(&TapConfig::new()).unwrap();
+    assert_eq!(sim.register(RegisterAddress::TAP_CFG0) & 0b0000_1110, 0);
+    assert_eq!(sim.register(RegisterAddress::TAP_CFG1), 0);
+    assert_eq!(sim.register(RegisterAddress::TAP_CFG2), 0b1000_0000);
+    assert_eq!(sim.register(RegisterAddress::WAKE_UP_THS), 0);
+}
+
+#[test]
+fn configure_tap_rejects_out_of_range_values() {
+    let (sim, mut imu) = setup();
+    sim.clear_writes();
+
+    let threshold = TapConfig::new().y_threshold(Some(TAP_THRESHOLD_MAX + 1));
+    let result = imu.configure_tap(&threshold);
+    assert!(matches!(result, Err(Lsm6dsrxError::InvalidConfig)));
+
+    let duration = TapConfig::new().z_threshold(Some(1)).duration(16);
+    let result = imu.configure_tap(&duration);
+    assert!(matches!(result, Err(Lsm6dsrxError::InvalidConfig)));
+
+    assert!(sim.writes().is_empty());
+}
+
+#[test]
+fn tap_event_decodes_tap_source() {
+    let (sim, mut imu) = setup();
+    assert_eq!(imu.tap_event().unwrap(), None);
+
+    // 軸と向きがあっても TAP_IA が立っていなければ検出していない
+    sim.set_register(RegisterAddress::TAP_SRC, 0b0010_0001);
+    assert_eq!(imu.tap_event().unwrap(), None);
+
+    sim.set_register(RegisterAddress::TAP_SRC, 0b0111_1010);
+    assert_eq!(
+        imu.tap_event().unwrap(),
+        Some(TapEvent {
+            kind: TapKind::Double,
+            axis: TapAxis::Y,
+            sign: TapSign::Negative,
+            timestamp: None,
+        })
+    );
+
+    imu.enable_timestamp(true).unwrap();
+    sim.set_timestamp(1234);
+    sim.set_register(RegisterAddress::TAP_SRC, 0b0110_0001);
+    assert_eq!(
+        imu.tap_event().unwrap(),
+        Some(TapEvent {
+            kind: TapKind::Single,
+            axis: TapAxis::Z,
+            sign: TapSign::Positive,
+            timestamp: Some(1234),
+        })
+    );
+}
+
+#[test]
+fn reads_without_auto_increment_repeat_one_register() {
+    let sim = Simulator::new();
+    sim.set_acceleration_raw([0x0102, 0x0304, 0x0506]);
+    let mut bus = SpiInterface::new(sim.clone());
+
+    let mut buf = [0u8; 2];
+    lsm6dsrx::RegisterBus::read_registers(&mut bus, RegisterAddress::OUTX_L_A, &mut buf).unwrap();
+    assert_eq!(buf, [0x02, 0x01]);
+
+    lsm6dsrx::RegisterBus::write_register(&mut bus, RegisterAddress::CTRL3_C, 0).unwrap();
+    lsm6dsrx::RegisterBus::read_registers(&mut bus, RegisterAddress::OUTX_L_A, &mut buf).unwrap();
+    assert_eq!(buf, [0x02, 0x02]);
+}
+
+#[test]
+fn drain_fifo_reports_overrun_after_reading() {
+    let (sim, mut imu) = setup();
+    sim.push_fifo(FifoTag::AccelNc, [1, 1, 1]);
+    sim.push_fifo(FifoTag::AccelNc, [2, 2, 2]);
+    sim.set_fifo_overrun(true);
+
+    let mut samples = Vec::new();
+    let result = imu.drain_fifo_with(|sample| samples.push(sample));
+    assert!(matches!(result, Err(Lsm6dsrxError::FifoOverrun)));
+    assert_eq!(samples.len(), 2);
+    assert_eq!(sim.fifo_len(), 0);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn drain_fifo_keeps_samples_on_overrun() {
+    let (sim, mut imu) = setup();
+    sim.push_fifo(FifoTag::AccelNc, [1, 1, 1]);
+    sim.push_fifo(FifoTag::GyroNc, [2, 2, 2]);
+    sim.set_fifo_overrun(true);
+
+    let drain = imu.drain_fifo().unwrap();
+    assert!(drain.overrun);
+    assert_eq!(drain.samples.len(), 2);
+    assert_eq!(sim.fifo_len(), 0);
+
+    sim.set_fifo_overrun(false);
+    sim.push_fifo(FifoTag::AccelNc, [3, 3, 3]);
+    let drain = imu.drain_fifo().unwrap();
+    assert!(!drain.overrun);
+    assert_eq!(drain.samples.len(), 1);
+}
+
+#[test]
+fn new_waits_for_software_reset() {
+    let sim = Simulator::new();
+    sim.set_reset_reads(Some(3));
+    let mut delay = SimDelay::default();
+
+    Lsm6sdrx::new(SpiInterface::new(sim.clone()), &mut delay).unwrap();
+    // 3 回分待つ
+    assert_eq!(delay.total_ns, 3 * 50_000);
+    // リセットのあとに設定を書き込んでいる
+    assert_eq!(sim.register(RegisterAddress::CTRL3_C), 0b0100_0100);
+    assert_eq!(sim.register(RegisterAddress::CTRL1_XL), 0b1000_0010);
+}
+
+#[test]
+fn new_times_out_when_reset_does_not_complete() {
+    let sim = Simulator::new();
+    sim.set_reset_reads(None);
+    let mut delay = SimDelay::default();
+
+    let result = Lsm6sdrx::new(SpiInterface::new(sim.clone()), &mut delay);
+    assert!(matches!(result, Err(Lsm6dsrxError::ResetTimeout)));
+    assert_eq!(delay.total_ns, 10_000_000);
+}
+
+#[test]
+fn reset_restores_config() {
+    let (sim, mut imu) = setup();
+    imu.set_accel_full_scale(AccelFullScale::G8).unwrap();
+    imu.enable_timestamp(true).unwrap();
+    // センサ側だけ設定が消えた状態
+    sim.set_register(RegisterAddress::CTRL1_XL, 0);
+    sim.set_register(RegisterAddress::CTRL10_C, 0);
+    sim.clear_writes();
+
+    imu.reset(&mut SimDelay::default()).unwrap();
+    assert_eq!(
+        sim.writes()[0],
+        (RegisterAddress::CTRL3_C.bits(), 0b0100_0101)
+    );
+    assert_eq!(sim.register(RegisterAddress::CTRL1_XL), 0b1000_1110);
+    assert_eq!(sim.register(RegisterAddress::CTRL10_C), 0b0010_0000);
+}
+
+#[test]
+fn reboot_memory_waits_for_boot() {
+    let (sim, mut imu) = setup();
+    sim.set_reset_reads(Some(2));
+    sim.clear_writes();
+    let mut delay = SimDelay::default();
+
+    imu.reboot_memory(&mut delay).unwrap();
+    assert_eq!(
+        sim.writes()[0],
+        (RegisterAddress::CTRL3_C.bits(), 0b1100_0100)
+    );
+    // boot time と、BOOT, SW_RESET をそれぞれ 2 回ずつ待つ
+    assert_eq!(delay.total_ns, 10_000_000 + 4 * 50_000);
+    assert_eq!(sim.register(RegisterAddress::CTRL3_C), 0b0100_0100);
+}
+
+#[test]
+fn reboot_memory_times_out() {
+    let (sim, mut imu) = setup();
+    sim.set_reset_reads(None);
+
+    let result = imu.reboot_memory(&mut SimDelay::default());
+    assert!(matches!(result, Err(Lsm6dsrxError::ResetTimeout)));
+}
+
+#[test]
+fn self_test_passes_within_limits() {
+    let (sim, mut imu) = setup();
+    sim.set_acceleration_raw([100, -100, 8200]);
+    sim.set_angular_rate_raw([10, 20, -30]);
+    // ±4g で 4100 LSB = 500.2mg, ±2000dps で 5000 LSB = 350dps
+    sim.set_self_test_response([4100, 4100, -4100], [5000, -5000, 5000]);
+    let mut delay = SimDelay::default();
+
+    let report = imu.self_test(&mut delay).unwrap();
+    for delta in report.accel_positive.iter().chain(&report.accel_negative) {
+        assert_close(*delta, 4100.0 * 0.122);
+    }
+    for delta in report.gyro_positive.iter().chain(&report.gyro_negative) {
+        assert_close(*delta, 5000.0 * 70.0);
+    }
+    assert!(report.passed());
+    assert!(report.check::<SimError>().is_ok());
+    // 6 回分の安定待ち
+    assert!(delay.total_ns >= 6 * 100_000_000);
+
+    // 元の設定に戻っている
+    assert_eq!(sim.register(RegisterAddress::CTRL5_C), 0);
+    assert_eq!(sim.register(RegisterAddress::CTRL1_XL), 0b1000_0010);
+    assert_eq!(sim.register(RegisterAddress::CTRL2_G), 0b1000_1100);
+}
+
+#[test]
+fn self_test_reports_out_of_range_axes() {
+    let (sim, mut imu) = setup();
+    // 加速度計の Z 軸だけ応答しない
+    sim.set_self_test_response([4100, 4100, 0], [5000, 5000, 5000]);
+
+    let report = imu.self_test(&mut SimDelay::default()).unwrap();
+    assert!(!report.accel_passed());
+    assert!(report.gyro_passed());
+    assert!(matches!(
+        report.check::<SimError>(),
+        Err(Lsm6dsrxError::SelfTestFailed)
+    ));
+}
+
+fn user_offset_registers(sim: &Simulator) -> [u8; 3] {
+    [
+        sim.register(RegisterAddress::X_OFS_USR),
+        sim.register(RegisterAddress::Y_OFS_USR),
+        sim.register(RegisterAddress::Z_OFS_USR),
+    ]
+}
+
+#[test]
+fn new_writes_configured_user_offset() {
+    let sim = Simulator::new();
+    let config = Lsm6dsrxConfig::new().accel_user_offset(AccelUserOffset::new(-1, -46, 5));
+    let imu = Lsm6sdrx::with_config(
+        SpiInterface::new(sim.clone()),
+        config,
+        &mut SimDelay::default(),
+    )
+    .unwrap();
+
+    assert_eq!(imu.accel_user_offset(), AccelUserOffset::new(-1, -46, 5));
+    assert_eq!(user_offset_registers(&sim), [0xFF, 0xD2, 0x05]);
+}
+
+#[test]
+fn set_accel_user_offset_writes_registers() {
+    let (sim, mut imu) = setup();
+    assert_eq!(user_offset_registers(&sim), [0, 0, 0]);
+
+    imu.set_accel_user_offset(AccelUserOffset::new(127, -128, 0))
+        .unwrap();
+    assert_eq!(user_offset_registers(&sim), [0x7F, 0x80, 0x00]);
+    assert_eq!(
+        imu.config().accel_user_offset,
+        AccelUserOffset::new(127, -128, 0)
+    );
+}
+
+#[test]
+fn calibrate_accel_offset_cancels_bias() {
+    let (sim, mut imu) = setup();
+    imu.set_accel_user_offset(AccelUserOffset::new(1, 2, 3))
+        .unwrap();
+    // ±2g: X = +10.004mg, Y = -5.002mg, Z = 1g + 20.008mg
+    sim.set_acceleration_raw([164, -82, 16393 + 328]);
+
+    let offset = imu
+        .calibrate_accel_offset(8, &mut SimDelay::default())
+        .unwrap();
+    // 2^-10 g/LSB
+    assert_eq!(offset, AccelUserOffset::new(10, -5, 20));
+    assert_eq!(imu.accel_user_offset(), offset);
+    assert_eq!(user_offset_registers(&sim), [10, (-5i8) as u8, 20]);
+}
+
+#[test]
+fn calibrate_accel_offset_uses_selected_weight() {
+    let (sim, mut imu) = setup();
+    imu.apply_config(&Lsm6dsrxConfig::new().accel_offset_weight(OffsetWeight::Pow2Minus6))
+        .unwrap();
+    // X = +305.0mg
+    sim.set_acceleration_raw([5000, 0, 16393]);
+
+    let offset = imu
+        .calibrate_accel_offset(4, &mut SimDelay::default())
+        .unwrap();
+    // 2^-6 g/LSB
+    assert_eq!(offset, AccelUserOffset::new(20, 0, 0));
+}
+
+#[test]
+fn calibrate_accel_offset_rejects_out_of_range_bias() {
+    let (sim, mut imu) = setup();
+    imu.set_accel_user_offset(AccelUserOffset::new(1, 2, 3))
+        .unwrap();
+    // X = +305.0mg は 2^-10 g/LSB では 312 LSB になる
+    sim.set_acceleration_raw([5000, 0, 16393]);
+
+    let result = imu.calibrate_accel_offset(4, &mut SimDelay::default());
+    assert!(matches!(result, Err(Lsm6dsrxError::InvalidConfig)));
+    // 元のオフセットに戻っている
+    assert_eq!(imu.accel_user_offset(), AccelUserOffset::new(1, 2, 3));
+    assert_eq!(user_offset_registers(&sim), [1, 2, 3]);
+}
+
+#[test]
+fn user_offset_from_bias_rounds_and_checks_range() {
+    let weight = OffsetWeight::Pow2Minus10;
+    let lsb = weight.sensitivity();
+    assert_eq!(
+        AccelUserOffset::from_bias([0.4 * lsb, -0.6 * lsb, 127.4 * lsb], weight),
+        Some(AccelUserOffset::new(0, -1, 127))
+    );
+    assert_eq!(
+        AccelUserOffset::from_bias([-127.4 * lsb, 0.0, 0.0], weight),
+        Some(AccelUserOffset::new(-127, 0, 0))
+    );
+    assert_eq!(
+        AccelUserOffset::from_bias([127.6 * lsb, 0.0, 0.0], weight),
+        None
+    );
+    assert_eq!(
+        AccelUserOffset::from_bias([0.0, -128.5 * lsb, 0.0], weight),
+        None
+    );
+}
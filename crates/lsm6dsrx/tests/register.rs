@@ -0,0 +1,111 @@
+use lsm6dsrx::{
+    AccelFilterCutoff, AccelFullScale, AccelOdr, CounterBdrReg1, Ctrl10C, Ctrl1Xl, Ctrl2G, Ctrl3C,
+    Ctrl4C, Ctrl5C, Ctrl6C, Ctrl7G, Ctrl8Xl, Ctrl9Xl, FifoCtrl2, FifoCtrl3, FifoCtrl4,
+    GyroFullScale, GyroLpf1Bandwidth, GyroOdr, Int1Ctrl, Int2Ctrl, IntDur2, Md1Cfg, Md2Cfg,
+    Register, TapCfg0, TapCfg1, TapCfg2, TapThs6d, WakeUpThs,
+};
+
+/// 同じビットを持つフラグがない
+fn assert_disjoint<R: Register>() {
+    let mut seen = 0u8;
+    for flag in R::FLAGS {
+        let bits = flag.value().bits();
+        assert_eq!(
+            seen & bits,
+            0,
+            "{} overlaps in register {:#04x}",
+            flag.name(),
+            R::ADDRESS.bits()
+        );
+        seen |= bits;
+    }
+}
+
+#[test]
+fn register_flags_do_not_overlap() {
+    assert_disjoint::<FifoCtrl2>();
+    assert_disjoint::<FifoCtrl3>();
+    assert_disjoint::<FifoCtrl4>();
+    assert_disjoint::<CounterBdrReg1>();
+    assert_disjoint::<Int1Ctrl>();
+    assert_disjoint::<Int2Ctrl>();
+    assert_disjoint::<Ctrl1Xl>();
+    assert_disjoint::<Ctrl2G>();
+    assert_disjoint::<Ctrl3C>();
+    assert_disjoint::<Ctrl4C>();
+    assert_disjoint::<Ctrl5C>();
+    assert_disjoint::<Ctrl6C>();
+    assert_disjoint::<Ctrl7G>();
+    assert_disjoint::<Ctrl8Xl>();
+    assert_disjoint::<Ctrl9Xl>();
+    assert_disjoint::<Ctrl10C>();
+    assert_disjoint::<TapCfg0>();
+    assert_disjoint::<TapCfg1>();
+    assert_disjoint::<TapCfg2>();
+    assert_disjoint::<TapThs6d>();
+    assert_disjoint::<IntDur2>();
+    assert_disjoint::<WakeUpThs>();
+    assert_disjoint::<Md1Cfg>();
+    assert_disjoint::<Md2Cfg>();
+}
+
+#[test]
+fn ctrl9_xl_reset_value_is_den_xyz() {
+    assert_eq!(
+        Ctrl9Xl::DEN_X | Ctrl9Xl::DEN_Y | Ctrl9Xl::DEN_Z,
+        Ctrl9Xl::from_bits_retain(0xE0)
+    );
+}
+
+#[test]
+fn ctrl1_xl_fields_round_trip() {
+    let mut reg = Ctrl1Xl::LPF2_XL_EN;
+    reg.set_odr(AccelOdr::Hz1_6);
+    reg.set_full_scale(AccelFullScale::G16);
+    assert_eq!(reg.bits(), 0b1011_0110);
+    assert_eq!(reg.odr(), Some(AccelOdr::Hz1_6));
+    assert_eq!(reg.full_scale(), AccelFullScale::G16);
+
+    reg.set_odr(AccelOdr::Hz104);
+    assert_eq!(reg.bits(), 0b0100_0110);
+    assert_eq!(reg.odr(), Some(AccelOdr::Hz104));
+
+    // 予約済みの値
+    assert_eq!(Ctrl1Xl::from_bits_retain(0b1100_0000).odr(), None);
+}
+
+#[test]
+fn ctrl2_g_fields_round_trip() {
+    let mut reg = Ctrl2G::empty();
+    for full_scale in [
+        GyroFullScale::Dps125,
+        GyroFullScale::Dps250,
+        GyroFullScale::Dps500,
+        GyroFullScale::Dps1000,
+        GyroFullScale::Dps2000,
+        GyroFullScale::Dps4000,
+    ] {
+        reg.set_full_scale(full_scale);
+        assert_eq!(reg.full_scale(), Some(full_scale));
+    }
+
+    reg.set_odr(GyroOdr::Hz6660);
+    assert_eq!(reg.bits(), 0b1010_0001);
+    assert_eq!(reg.odr(), Some(GyroOdr::Hz6660));
+
+    // FS_125 と FS_4000 を同時に立てることはできない
+    assert_eq!(Ctrl2G::from_bits_retain(0b0000_0011).full_scale(), None);
+}
+
+#[test]
+fn filter_fields_round_trip() {
+    let mut ctrl8 = Ctrl8Xl::LOW_PASS_ON_6D;
+    ctrl8.set_hpcf(AccelFilterCutoff::OdrDiv800);
+    assert_eq!(ctrl8.bits(), 0b1110_0001);
+    assert_eq!(ctrl8.hpcf(), AccelFilterCutoff::OdrDiv800);
+
+    let mut ctrl6 = Ctrl6C::USR_OFF_W;
+    ctrl6.set_ftype(GyroLpf1Bandwidth::Ftype5);
+    assert_eq!(ctrl6.bits(), 0b0000_1101);
+    assert_eq!(ctrl6.ftype(), GyroLpf1Bandwidth::Ftype5);
+}
@@ -0,0 +1,512 @@
+//! レジスタ単位で LSM6DSRX を真似する SPI デバイス
+//!
+//! - アドレスの MSB が 1 なら読み出し、0 なら書き込み
+//! - `CTRL3_C` の `IF_INC` が立っていればアドレスを自動インクリメントする
+//! - `CTRL3_C` の `SW_RESET` / `BOOT` は、設定した回数だけ読まれたあとに 0 に戻る
+//! - 出力レジスタ、タイムスタンプ、FIFO の中身はテストから差し込む
+//! - `CTRL5_C` でセルフテストを有効にすると、出力に設定した応答が加わる
+//! - 同期と非同期のどちらの `SpiDevice` としても使える
+
+#![allow(dead_code)]
+
+use std::{cell::RefCell, collections::VecDeque, fmt, rc::Rc};
+
+use embedded_hal::{
+    delay::DelayNs,
+    spi::{self, ErrorKind, ErrorType, Operation, SpiDevice},
+};
+use lsm6dsrx::RegisterAddress;
+
+/// レジスタファイルの大きさ
+pub const REGISTER_COUNT: usize = 128;
+
+/// 読み出しを表すアドレスのビット
+const READ_BIT: u8 = 0x80;
+
+/// `CTRL3_C`: `BOOT`
+const CTRL3_C_BOOT: u8 = 0b1000_0000;
+/// `CTRL3_C`: `IF_INC`
+const CTRL3_C_IF_INC: u8 = 0b0000_0100;
+/// `CTRL3_C`: `SW_RESET`
+const CTRL3_C_SW_RESET: u8 = 0b0000_0001;
+
+/// `FIFO_STATUS2`: `FIFO_WTM_IA`
+const FIFO_STATUS2_WTM_IA: u8 = 0b1000_0000;
+/// `FIFO_STATUS2`: `FIFO_OVR_IA`
+const FIFO_STATUS2_OVR_IA: u8 = 0b0100_0000;
+/// `FIFO_STATUS2`: `FIFO_FULL_IA`
+const FIFO_STATUS2_FULL_IA: u8 = 0b0010_0000;
+
+/// `STATUS_REG` のアドレス
+const STATUS_REG: usize = 0x1E;
+/// `STATUS_REG`: `TDA`
+const STATUS_REG_TDA: u8 = 0b0000_0100;
+/// `STATUS_REG`: `GDA`
+const STATUS_REG_GDA: u8 = 0b0000_0010;
+/// `STATUS_REG`: `XLDA`
+const STATUS_REG_XLDA: u8 = 0b0000_0001;
+
+/// 出力レジスタのアドレスに対応する `STATUS_REG` のビット
+fn data_ready_flag(addr: usize) -> u8 {
+    match addr {
+        0x20..=0x21 => STATUS_REG_TDA,
+        0x22..=0x27 => STATUS_REG_GDA,
+        0x28..=0x2D => STATUS_REG_XLDA,
+        _ => 0,
+    }
+}
+
+/// `TIMESTAMP2` に書き込むとタイムスタンプカウンタがリセットされる値
+const TIMESTAMP_RESET: u8 = 0xAA;
+
+/// FIFO のタグ (`TAG_SENSOR[4:0]`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FifoTag {
+    GyroNc = 0x01,
+    AccelNc = 0x02,
+    Temperature = 0x03,
+    Timestamp = 0x04,
+}
+
+/// FIFO の 1 ワード (`FIFO_DATA_OUT_TAG` から `FIFO_DATA_OUT_Z_H` まで)
+pub type FifoWord = [u8; 7];
+
+/// Table 20. Registers addresses map のデフォルト値
+fn reset_values() -> [u8; REGISTER_COUNT] {
+    let mut regs = [0u8; REGISTER_COUNT];
+    regs[RegisterAddress::PIN_CTRL.bits() as usize] = 0x3F;
+    regs[RegisterAddress::WHO_AM_I.bits() as usize] = 0x6B;
+    regs[RegisterAddress::CTRL3_C.bits() as usize] = CTRL3_C_IF_INC;
+    regs[RegisterAddress::CTRL9_XL.bits() as usize] = 0xE0;
+    regs
+}
+
+/// 出力レジスタの書き込みを受け付けないアドレス
+fn is_read_only(addr: u8) -> bool {
+    matches!(addr, 0x0F | 0x1A..=0x2D | 0x35..=0x3B | 0x40 | 0x41 | 0x43 | 0x78..=0x7E)
+}
+
+/// シミュレータのエラー
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SimError {
+    /// テストから注入したバスのエラー
+    Injected,
+    /// 対応していない `Operation`
+    Unsupported,
+}
+
+impl fmt::Display for SimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl spi::Error for SimError {
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::Other
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    registers: [u8; REGISTER_COUNT],
+    timestamp: u32,
+    fifo: VecDeque<FifoWord>,
+    fifo_overrun: bool,
+    /// 書き込まれた (アドレス, 値) の履歴
+    writes: Vec<(u8, u8)>,
+    /// 残りのトランザクションをすべて失敗させる
+    fault: bool,
+    /// 実行中の `SW_RESET` / `BOOT`
+    pending: u8,
+    /// `pending` が完了するまでに `CTRL3_C` が読まれる回数 (`None` なら完了しない)
+    pending_reads: Option<usize>,
+    /// `SW_RESET` / `BOOT` を書き込んだときの `pending_reads`
+    reset_reads: Option<usize>,
+    /// 正のセルフテストで加速度の出力に加わる値 [LSB]
+    accel_self_test: [i16; 3],
+    /// 正のセルフテストで角速度の出力に加わる値 [LSB]
+    gyro_self_test: [i16; 3],
+    /// 書き込んでも値が変わらないビット (アドレスごと)
+    stuck_bits: [u8; REGISTER_COUNT],
+}
+
+impl State {
+    fn new() -> State {
+        State {
+            registers: reset_values(),
+            timestamp: 0,
+            fifo: VecDeque::new(),
+            fifo_overrun: false,
+            writes: Vec::new(),
+            fault: false,
+            pending: 0,
+            pending_reads: Some(0),
+            reset_reads: Some(0),
+            accel_self_test: [0; 3],
+            gyro_self_test: [0; 3],
+            stuck_bits: [0; REGISTER_COUNT],
+        }
+    }
+
+    fn auto_increment(&self) -> bool {
+        self.registers[RegisterAddress::CTRL3_C.bits() as usize] & CTRL3_C_IF_INC != 0
+    }
+
+    fn next_address(&self, addr: u8) -> u8 {
+        if !self.auto_increment() {
+            return addr;
+        }
+        // FIFO の出力は 1 ワードの中で回る
+        match addr {
+            0x7E => 0x78,
+            addr => (addr + 1) % REGISTER_COUNT as u8,
+        }
+    }
+
+    fn read(&mut self, addr: u8) -> u8 {
+        let addr = addr as usize;
+        match addr {
+            0x3A => (self.fifo.len() & 0xFF) as u8,
+            0x3B => {
+                let unread = self.fifo.len();
+                let watermark = self.watermark();
+                let mut status = ((unread >> 8) & 0b11) as u8;
+                if watermark != 0 && unread >= watermark {
+                    status |= FIFO_STATUS2_WTM_IA;
+                }
+                if self.fifo_overrun {
+                    status |= FIFO_STATUS2_OVR_IA;
+                }
+                if unread >= 512 {
+                    status |= FIFO_STATUS2_FULL_IA;
+                }
+                status
+            }
+            0x12 if self.pending != 0 => match self.pending_reads {
+                Some(0) => {
+                    self.complete_pending();
+                    self.registers[addr]
+                }
+                Some(ref mut reads) => {
+                    *reads -= 1;
+                    self.registers[addr] | self.pending
+                }
+                None => self.registers[addr] | self.pending,
+            },
+            0x20..=0x21 => {
+                self.registers[STATUS_REG] &= !data_ready_flag(addr);
+                self.registers[addr]
+            }
+            0x22..=0x2D => {
+                // 読んだ出力は新しいデータではなくなる
+                self.registers[STATUS_REG] &= !data_ready_flag(addr);
+                let base =
+                    i16::from_le_bytes([self.registers[addr & !1], self.registers[addr | 1]]);
+                let value = base.wrapping_add(self.self_test_delta(addr));
+                value.to_le_bytes()[addr & 1]
+            }
+            0x40..=0x43 => self.timestamp.to_le_bytes()[addr - 0x40],
+            _ => self.registers[addr],
+        }
+    }
+
+    fn write(&mut self, addr: u8, data: u8) {
+        self.writes.push((addr, data));
+
+        if addr == RegisterAddress::TIMESTAMP2.bits() {
+            if data == TIMESTAMP_RESET {
+                self.timestamp = 0;
+            }
+            return;
+        }
+        if is_read_only(addr) {
+            return;
+        }
+        let stuck = self.stuck_bits[addr as usize];
+        let data = (data & !stuck) | (self.registers[addr as usize] & stuck);
+
+        if addr == RegisterAddress::CTRL3_C.bits() {
+            self.pending |= data & (CTRL3_C_BOOT | CTRL3_C_SW_RESET);
+            self.pending_reads = self.reset_reads;
+            self.registers[addr as usize] = data & !(CTRL3_C_BOOT | CTRL3_C_SW_RESET);
+            if self.pending_reads == Some(0) {
+                self.complete_pending();
+            }
+            return;
+        }
+
+        self.registers[addr as usize] = data;
+    }
+
+    /// `SW_RESET` / `BOOT` を完了させる
+    fn complete_pending(&mut self) {
+        if self.pending & CTRL3_C_SW_RESET != 0 {
+            // ソフトウェアリセットは制御レジスタをデフォルト値に戻す
+            let who_am_i = self.registers[RegisterAddress::WHO_AM_I.bits() as usize];
+            self.registers = reset_values();
+            self.registers[RegisterAddress::WHO_AM_I.bits() as usize] = who_am_i;
+        }
+        self.pending = 0;
+    }
+
+    /// セルフテストで `addr` の出力に加わる値
+    fn self_test_delta(&self, addr: usize) -> i16 {
+        let ctrl5_c = self.registers[RegisterAddress::CTRL5_C.bits() as usize];
+        let axis = (addr - 0x22) / 2 % 3;
+        if addr >= RegisterAddress::OUTX_L_A.bits() as usize {
+            match ctrl5_c & 0b11 {
+                0b01 => self.accel_self_test[axis],
+                0b10 => -self.accel_self_test[axis],
+                _ => 0,
+            }
+        } else {
+            match (ctrl5_c >> 2) & 0b11 {
+                0b01 => self.gyro_self_test[axis],
+                0b11 => -self.gyro_self_test[axis],
+                _ => 0,
+            }
+        }
+    }
+
+    fn watermark(&self) -> usize {
+        let low = self.registers[RegisterAddress::FIFO_CTRL1.bits() as usize] as usize;
+        let high = (self.registers[RegisterAddress::FIFO_CTRL2.bits() as usize] & 0b1) as usize;
+        high << 8 | low
+    }
+
+    /// FIFO の出力レジスタに次のワードを出す
+    fn load_fifo_word(&mut self) {
+        let word = self.fifo.pop_front().unwrap_or_default();
+        let start = RegisterAddress::FIFO_DATA_OUT_TAG.bits() as usize;
+        self.registers[start..start + word.len()].copy_from_slice(&word);
+    }
+}
+
+/// LSM6DSRX のシミュレータ
+///
+/// ドライバに渡したあともテストから中身を見られるように、状態は clone 間で共有する
+#[derive(Debug, Clone)]
+pub struct Simulator {
+    state: Rc<RefCell<State>>,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Simulator::new()
+    }
+}
+
+impl Simulator {
+    pub fn new() -> Simulator {
+        Simulator {
+            state: Rc::new(RefCell::new(State::new())),
+        }
+    }
+
+    /// レジスタの値をそのまま返す
+    pub fn register(&self, addr: RegisterAddress) -> u8 {
+        self.state.borrow().registers[addr.bits() as usize]
+    }
+
+    /// レジスタの値をそのまま書き換える (読み出し専用のレジスタも書ける)
+    pub fn set_register(&self, addr: RegisterAddress, value: u8) {
+        self.state.borrow_mut().registers[addr.bits() as usize] = value;
+    }
+
+    /// 書き込まれた (アドレス, 値) の履歴
+    pub fn writes(&self) -> Vec<(u8, u8)> {
+        self.state.borrow().writes.clone()
+    }
+
+    /// 書き込みの履歴を消す
+    pub fn clear_writes(&self) {
+        self.state.borrow_mut().writes.clear();
+    }
+
+    /// `addr` の `mask` のビットを、書き込んでも変わらないようにする
+    pub fn set_stuck_bits(&self, addr: RegisterAddress, mask: u8) {
+        self.state.borrow_mut().stuck_bits[addr.bits() as usize] = mask;
+    }
+
+    /// 以降のトランザクションをすべて失敗させる
+    pub fn set_fault(&self, fault: bool) {
+        self.state.borrow_mut().fault = fault;
+    }
+
+    /// `SW_RESET` / `BOOT` が 0 に戻るまでに `CTRL3_C` が読まれる回数を設定する
+    ///
+    /// `None` にすると 0 に戻らない
+    pub fn set_reset_reads(&self, reads: Option<usize>) {
+        self.state.borrow_mut().reset_reads = reads;
+    }
+
+    /// 正のセルフテストで出力に加わる値を設定する (負のときは符号が反転する)
+    pub fn set_self_test_response(&self, accel: [i16; 3], gyro: [i16; 3]) {
+        let mut state = self.state.borrow_mut();
+        state.accel_self_test = accel;
+        state.gyro_self_test = gyro;
+    }
+
+    /// 温度の出力レジスタに生の値を入れる
+    pub fn set_temperature_raw(&self, raw: i16) {
+        self.set_output(RegisterAddress::OUT_TEMP_L, &[raw]);
+    }
+
+    /// 角速度の出力レジスタに生の値を入れる
+    pub fn set_angular_rate_raw(&self, raw: [i16; 3]) {
+        self.set_output(RegisterAddress::OUTX_L_G, &raw);
+    }
+
+    /// 加速度の出力レジスタに生の値を入れる
+    pub fn set_acceleration_raw(&self, raw: [i16; 3]) {
+        self.set_output(RegisterAddress::OUTX_L_A, &raw);
+    }
+
+    /// タイムスタンプカウンタの値
+    pub fn timestamp(&self) -> u32 {
+        self.state.borrow().timestamp
+    }
+
+    /// タイムスタンプカウンタの値を設定する
+    pub fn set_timestamp(&self, timestamp: u32) {
+        self.state.borrow_mut().timestamp = timestamp;
+    }
+
+    /// FIFO にワードを積む
+    pub fn push_fifo(&self, tag: FifoTag, data: [i16; 3]) {
+        let mut word = [0u8; 7];
+        word[0] = (tag as u8) << 3;
+        for (i, value) in data.iter().enumerate() {
+            word[1 + i * 2..3 + i * 2].copy_from_slice(&value.to_le_bytes());
+        }
+        self.push_fifo_raw(word);
+    }
+
+    /// FIFO にワードをそのまま積む
+    pub fn push_fifo_raw(&self, word: FifoWord) {
+        self.state.borrow_mut().fifo.push_back(word);
+    }
+
+    /// FIFO にタイムスタンプのワードを積む
+    pub fn push_fifo_timestamp(&self, timestamp: u32) {
+        let low = timestamp as u16 as i16;
+        let high = (timestamp >> 16) as u16 as i16;
+        self.push_fifo(FifoTag::Timestamp, [low, high, 0]);
+    }
+
+    /// FIFO に残っているワード数
+    pub fn fifo_len(&self) -> usize {
+        self.state.borrow().fifo.len()
+    }
+
+    /// `FIFO_OVR_IA` を立てる
+    pub fn set_fifo_overrun(&self, overrun: bool) {
+        self.state.borrow_mut().fifo_overrun = overrun;
+    }
+
+    fn set_output(&self, addr: RegisterAddress, values: &[i16]) {
+        let mut state = self.state.borrow_mut();
+        let start = addr.bits() as usize;
+        for (i, value) in values.iter().enumerate() {
+            state.registers[start + i * 2..start + i * 2 + 2].copy_from_slice(&value.to_le_bytes());
+        }
+        // 新しい変換結果が出力されたことにする
+        state.registers[STATUS_REG] |= data_ready_flag(start);
+    }
+}
+
+impl ErrorType for Simulator {
+    type Error = SimError;
+}
+
+impl Simulator {
+    /// 同期と非同期の [`SpiDevice`] で共通のトランザクション
+    fn run_transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), SimError> {
+        let mut state = self.state.borrow_mut();
+        if state.fault {
+            return Err(SimError::Injected);
+        }
+
+        // 最初の 1 バイトがアドレス
+        let mut address = None;
+        for operation in operations {
+            match operation {
+                Operation::Write(bytes) => {
+                    let mut bytes = bytes.iter().copied();
+                    if address.is_none() {
+                        let Some(first) = bytes.next() else {
+                            continue;
+                        };
+                        let read = first & READ_BIT != 0;
+                        let addr = first & !READ_BIT;
+                        if read && addr == RegisterAddress::FIFO_DATA_OUT_TAG.bits() {
+                            state.load_fifo_word();
+                        }
+                        address = Some((addr, read));
+                    }
+                    for byte in bytes {
+                        let Some((addr, read)) = address else {
+                            unreachable!();
+                        };
+                        if read {
+                            return Err(SimError::Unsupported);
+                        }
+                        state.write(addr, byte);
+                        address = Some((state.next_address(addr), read));
+                    }
+                }
+                Operation::Read(buf) => {
+                    let Some((mut addr, true)) = address else {
+                        return Err(SimError::Unsupported);
+                    };
+                    for byte in buf.iter_mut() {
+                        *byte = state.read(addr);
+                        addr = state.next_address(addr);
+                    }
+                    address = Some((addr, true));
+                }
+                Operation::DelayNs(_) => {}
+                Operation::Transfer(_, _) | Operation::TransferInPlace(_) => {
+                    return Err(SimError::Unsupported);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl SpiDevice for Simulator {
+    fn transaction(&mut self, operations: &mut [Operation<'_, u8>]) -> Result<(), Self::Error> {
+        self.run_transaction(operations)
+    }
+}
+
+impl embedded_hal_async::spi::SpiDevice for Simulator {
+    async fn transaction(
+        &mut self,
+        operations: &mut [Operation<'_, u8>],
+    ) -> Result<(), Self::Error> {
+        self.run_transaction(operations)
+    }
+}
+
+/// 待った時間を記録するだけの [`DelayNs`]
+#[derive(Debug, Default)]
+pub struct SimDelay {
+    /// 待った時間の合計 [ns]
+    pub total_ns: u64,
+}
+
+impl DelayNs for SimDelay {
+    fn delay_ns(&mut self, ns: u32) {
+        self.total_ns += ns as u64;
+    }
+}
+
+impl embedded_hal_async::delay::DelayNs for SimDelay {
+    async fn delay_ns(&mut self, ns: u32) {
+        self.total_ns += ns as u64;
+    }
+}
@@ -1,8 +1,122 @@
+#![no_std]
+
 use serde::{Deserialize, Serialize};
 
+/// タイムスタンプカウンタの分解能 [µs/LSB]
+pub const TIMESTAMP_RESOLUTION_US: f64 = 25.0;
+
+/// 3軸の加速度 [mg]
 #[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
 pub struct Acceleration {
     pub x: f64,
     pub y: f64,
     pub z: f64,
+    /// センサのタイムスタンプカウンタの値 [LSB]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u32>,
+}
+
+/// 3軸の角速度 [mdps]
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct AngularRate {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// センサのタイムスタンプカウンタの値 [LSB]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u32>,
+}
+
+/// 温度
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Temperature {
+    /// [℃]
+    pub celsius: f64,
+}
+
+/// 同じ出力周期で取得した加速度、角速度、温度
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct MotionSample {
+    pub acceleration: Acceleration,
+    pub angular_rate: AngularRate,
+    /// [℃]
+    pub temperature: f64,
+}
+
+/// タップの種類
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum TapKind {
+    Single,
+    Double,
+}
+
+/// タップを検出した軸
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum TapAxis {
+    X,
+    Y,
+    Z,
+}
+
+/// タップを検出したときの加速度の向き
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum TapSign {
+    Positive,
+    Negative,
+}
+
+/// 検出したタップ
+#[derive(Deserialize, Serialize, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TapEvent {
+    pub kind: TapKind,
+    pub axis: TapAxis,
+    pub sign: TapSign,
+    /// センサのタイムスタンプカウンタの値 [LSB]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<u32>,
+}
+
+/// 加速度計の補正
+///
+/// 補正後の値は `matrix * (測定値 - bias)` になる。
+/// `matrix` で各軸の感度のずれと軸間の傾きを、`bias` でゼロ点のずれを打ち消す
+#[derive(Deserialize, Serialize, PartialEq, Clone, Debug)]
+pub struct Calibration {
+    /// 補正行列 (行ごと)
+    pub matrix: [[f64; 3]; 3],
+    /// ゼロ点のずれ [mg]
+    pub bias: [f64; 3],
+}
+
+impl Default for Calibration {
+    /// 何も補正しない
+    fn default() -> Self {
+        Self {
+            matrix: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            bias: [0.0; 3],
+        }
+    }
+}
+
+impl Calibration {
+    /// 加速度を補正する
+    pub fn apply(&self, acceleration: Acceleration) -> Acceleration {
+        let v = [
+            acceleration.x - self.bias[0],
+            acceleration.y - self.bias[1],
+            acceleration.z - self.bias[2],
+        ];
+        let [x, y, z] = self
+            .matrix
+            .map(|row| row[0] * v[0] + row[1] * v[1] + row[2] * v[2]);
+        Acceleration {
+            x,
+            y,
+            z,
+            ..acceleration
+        }
+    }
 }
@@ -0,0 +1,146 @@
+//! NVS に保存する設定
+//!
+//! ESP-IDF に依存しないので、エンコード/デコードはホストでテストできる
+//!
+//! blob の形式は `[バージョン (1 byte)][JSON]`。
+//! フォーマットを変えるときは [`SETTINGS_VERSION`] を上げて、古いバージョンを読めるように
+//! [`Settings::decode`] に移行処理を追加すること
+
+use std::fmt;
+
+use lsm6dsrx::{
+    AccelBatchRate, FifoConfig, FifoMode, GyroBatchRate, Lsm6dsrxConfig, TapConfig, TimestampBatch,
+};
+use schema::Calibration;
+use serde::{Deserialize, Serialize};
+
+/// 現在の blob のバージョン
+///
+/// - 1: タイムスタンプを使わない
+/// - 2: タイムスタンプカウンタを有効にし、FIFO にも入れる。FIFO はデフォルトで continuous mode
+pub const SETTINGS_VERSION: u8 = 2;
+
+/// デフォルトの FIFO の watermark [word]
+///
+/// 加速度、角速度、タイムスタンプの 3 word を 32 組溜めるごとに読む (1.66kHz で約 19ms ごと)。
+/// FIFO (512 word) が溢れるまでには 100ms ほど余裕がある
+pub const DEFAULT_FIFO_WATERMARK: u16 = 3 * 32;
+
+/// 保存できる blob の最大サイズ [byte]
+///
+/// NVS の 1 ページ (エントリ 126 個、4032 byte) のうち、ヘッダと他のキーの分を残した大きさ
+pub const SETTINGS_BLOB_MAX: usize = 3968;
+
+/// 保存する設定
+///
+/// 足りないフィールドはデフォルト値になる
+///
+/// デフォルトではタイムスタンプカウンタを有効にして、加速度と角速度を出力レートのまま
+/// タイムスタンプと一緒に FIFO に入れ、watermark ごとに読む
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// センサの設定 (キャリブレーションしたオフセットを含む)
+    pub sensor: Lsm6dsrxConfig,
+    /// 6面キャリブレーションで求めた加速度計の補正
+    pub accel_calibration: Calibration,
+    /// FIFO の設定。[`FifoMode::Bypass`] 以外なら watermark ごとに読む
+    pub fifo: FifoConfig,
+    /// タップの検出。洗濯物を入れたときなどに本体を叩いて印を付ける
+    pub tap: TapConfig,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            sensor: Lsm6dsrxConfig::new().timestamp(true),
+            accel_calibration: Calibration::default(),
+            fifo: FifoConfig::new()
+                .watermark(DEFAULT_FIFO_WATERMARK)
+                .mode(FifoMode::Continuous)
+                .accel_batch_rate(AccelBatchRate::Hz1667)
+                .gyro_batch_rate(GyroBatchRate::Hz1667)
+                .timestamp_batch(TimestampBatch::Every1),
+            tap: TapConfig::default(),
+        }
+    }
+}
+
+/// blob を読めなかった理由
+#[derive(Debug)]
+pub enum DecodeError {
+    /// 空の blob
+    Empty,
+    /// 知らないバージョン
+    UnsupportedVersion(u8),
+    /// 中身が壊れている
+    Corrupt(serde_json::Error),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Empty => write!(f, "Empty settings blob"),
+            DecodeError::UnsupportedVersion(version) => {
+                write!(f, "Unsupported settings version: {version}")
+            }
+            DecodeError::Corrupt(error) => write!(f, "Corrupt settings blob: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DecodeError::Corrupt(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl Settings {
+    /// 現在のバージョンの blob にする
+    pub fn encode(&self) -> Result<Vec<u8>, serde_json::Error> {
+        let mut blob = vec![SETTINGS_VERSION];
+        serde_json::to_writer(&mut blob, self)?;
+        Ok(blob)
+    }
+
+    /// blob から読む
+    pub fn decode(blob: &[u8]) -> Result<Settings, DecodeError> {
+        let (&version, payload) = blob.split_first().ok_or(DecodeError::Empty)?;
+        match version {
+            SETTINGS_VERSION => serde_json::from_slice(payload).map_err(DecodeError::Corrupt),
+            1 => {
+                // バージョン 1 ではタイムスタンプを設定できなかったので、現在のデフォルトにそろえる
+                let mut settings: Settings =
+                    serde_json::from_slice(payload).map_err(DecodeError::Corrupt)?;
+                let default = Settings::default();
+                settings.sensor.timestamp = default.sensor.timestamp;
+                if settings.fifo == FifoConfig::default() {
+                    // 当時のデフォルトの bypass mode のままなら、現在のデフォルトの FIFO を使う
+                    settings.fifo = default.fifo;
+                } else {
+                    settings.fifo.timestamp_batch = default.fifo.timestamp_batch;
+                }
+                Ok(settings)
+            }
+            version => Err(DecodeError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// blob から読む。blob がない、または読めない場合はデフォルト値を返す
+    pub fn decode_or_default(blob: Option<&[u8]>) -> Settings {
+        let Some(blob) = blob else {
+            log::info!("Settings not found, using defaults.");
+            return Settings::default();
+        };
+        match Settings::decode(blob) {
+            Ok(settings) => settings,
+            Err(error) => {
+                log::warn!("{error}, using defaults.");
+                Settings::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use lsm6dsrx::{
+    AccelBatchRate, AccelFullScale, AccelOdr, AccelUserOffset, FifoConfig, FifoMode, GyroBatchRate,
+    GyroOdr, Lsm6dsrxConfig, TapConfig, TapMode, TapPriority, TimestampBatch, FIFO_WATERMARK_MAX,
+    TAP_THRESHOLD_MAX,
+};
+use schema::Calibration;
+use settings::{DecodeError, Settings, SETTINGS_BLOB_MAX, SETTINGS_VERSION};
+
+fn calibrated() -> Settings {
+    Settings {
+        sensor: Lsm6dsrxConfig::new()
+            .accel_odr(AccelOdr::Hz104)
+            .accel_full_scale(AccelFullScale::G8)
+            .accel_lpf2(false)
+            .accel_user_offset(AccelUserOffset::new(-1, -46, 5))
+            .gyro_odr(GyroOdr::Hz208),
+        accel_calibration: Calibration {
+            matrix: [[0.98, 0.01, 0.0], [-0.01, 1.03, 0.02], [0.0, 0.0, 0.95]],
+            bias: [12.5, -3.0, 40.25],
+        },
+        fifo: FifoConfig::new()
+            .watermark(64)
+            .mode(FifoMode::Continuous)
+            .accel_batch_rate(AccelBatchRate::Hz104)
+            .gyro_batch_rate(GyroBatchRate::Hz208),
+        tap: TapConfig::new()
+            .z_threshold(Some(12))
+            .shock(2)
+            .quiet(1)
+            .duration(7)
+            .mode(TapMode::SingleAndDouble),
+    }
+}
+
+#[test]
+fn encode_decode_round_trip() {
+    let settings = calibrated();
+    let blob = settings.encode().unwrap();
+
+    assert_eq!(blob[0], SETTINGS_VERSION);
+    assert_eq!(Settings::decode(&blob).unwrap(), settings);
+}
+
+#[test]
+fn encoded_settings_fit_in_blob_max() {
+    // JSON で最も長くなる f64
+    let long = -2.2250738585072014e-308;
+    let mut settings = calibrated();
+    settings.accel_calibration = Calibration {
+        matrix: [[long; 3]; 3],
+        bias: [long; 3],
+    };
+    settings.sensor = settings
+        .sensor
+        .accel_user_offset(AccelUserOffset::new(-128, -128, -128));
+    settings.fifo = settings.fifo.watermark(FIFO_WATERMARK_MAX);
+    settings.tap = settings
+        .tap
+        .x_threshold(Some(TAP_THRESHOLD_MAX))
+        .y_threshold(Some(TAP_THRESHOLD_MAX))
+        .z_threshold(Some(TAP_THRESHOLD_MAX))
+        .priority(TapPriority::Zxy);
+
+    let blob = settings.encode().unwrap();
+    assert!(
+        blob.len() <= SETTINGS_BLOB_MAX,
+        "{} bytes exceeds {SETTINGS_BLOB_MAX}",
+        blob.len()
+    );
+}
+
+#[test]
+fn decode_rejects_empty_blob() {
+    assert!(matches!(Settings::decode(&[]), Err(DecodeError::Empty)));
+}
+
+#[test]
+fn decode_rejects_unknown_version() {
+    let mut blob = calibrated().encode().unwrap();
+    blob[0] = SETTINGS_VERSION + 1;
+
+    assert!(matches!(
+        Settings::decode(&blob),
+        Err(DecodeError::UnsupportedVersion(version)) if version == SETTINGS_VERSION + 1
+    ));
+}
+
+#[test]
+fn decode_rejects_corrupt_payload() {
+    let mut blob = calibrated().encode().unwrap();
+    blob.truncate(blob.len() / 2);
+
+    assert!(matches!(
+        Settings::decode(&blob),
+        Err(DecodeError::Corrupt(_))
+    ));
+}
+
+#[test]
+fn decode_fills_missing_fields_with_defaults() {
+    let mut blob = vec![SETTINGS_VERSION];
+    blob.extend_from_slice(
+        br#"{"sensor":{"accel_odr":"Hz52","accel_user_offset":{"x":3,"y":0,"z":-2}}}"#,
+    );
+
+    let settings = Settings::decode(&blob).unwrap();
+    assert_eq!(
+        settings.sensor,
+        Lsm6dsrxConfig::new()
+            .accel_odr(AccelOdr::Hz52)
+            .accel_user_offset(AccelUserOffset::new(3, 0, -2))
+    );
+    assert_eq!(settings.accel_calibration, Calibration::default());
+    assert_eq!(settings.fifo, Settings::default().fifo);
+    assert!(!settings.tap.is_enabled());
+}
+
+#[test]
+fn default_enables_timestamps() {
+    let settings = Settings::default();
+    assert!(settings.sensor.timestamp);
+    assert_eq!(settings.fifo.timestamp_batch, TimestampBatch::Every1);
+}
+
+#[test]
+fn default_reads_fifo_at_watermark() {
+    let settings = Settings::default();
+    assert_eq!(settings.fifo.mode, FifoMode::Continuous);
+    assert!(settings.fifo.watermark > 0);
+    assert!(settings.fifo.watermark <= FIFO_WATERMARK_MAX);
+    assert_eq!(settings.fifo.accel_batch_rate, AccelBatchRate::Hz1667);
+    assert_eq!(settings.fifo.gyro_batch_rate, GyroBatchRate::Hz1667);
+}
+
+#[test]
+fn decode_migrates_version_1_to_timestamps() {
+    let mut blob = vec![1];
+    blob.extend_from_slice(
+        br#"{"sensor":{"accel_odr":"Hz52"},"fifo":{"watermark":64,"timestamp_batch":"NotBatched"}}"#,
+    );
+
+    let settings = Settings::decode(&blob).unwrap();
+    assert_eq!(settings.sensor.accel_odr, AccelOdr::Hz52);
+    assert!(settings.sensor.timestamp);
+    assert_eq!(settings.fifo.watermark, 64);
+    assert_eq!(settings.fifo.timestamp_batch, TimestampBatch::Every1);
+}
+
+#[test]
+fn decode_migrates_version_1_bypass_fifo_to_default() {
+    let mut blob = vec![1];
+    serde_json::to_writer(
+        &mut blob,
+        &Settings {
+            sensor: Lsm6dsrxConfig::new(),
+            fifo: FifoConfig::new(),
+            ..calibrated()
+        },
+    )
+    .unwrap();
+
+    let settings = Settings::decode(&blob).unwrap();
+    assert_eq!(settings.fifo, Settings::default().fifo);
+    assert_eq!(settings.tap, calibrated().tap);
+}
+
+#[test]
+fn decode_or_default_falls_back() {
+    assert_eq!(Settings::decode_or_default(None), Settings::default());
+    assert_eq!(
+        Settings::decode_or_default(Some(&[SETTINGS_VERSION, b'{'])),
+        Settings::default()
+    );
+
+    let blob = calibrated().encode().unwrap();
+    assert_eq!(Settings::decode_or_default(Some(&blob)), calibrated());
+}
@@ -1,5 +1,11 @@
+use std::fmt::Debug;
+
 use anyhow::Result;
-use schema::Acceleration as AccelerationData;
+use lsm6dsrx::{Lsm6sdrx, RegisterBus};
+use schema::{
+    Acceleration as AccelerationData, AngularRate as AngularRateData,
+    Temperature as TemperatureData,
+};
 use serde::Serialize;
 
 /// 3軸の加速度を返す
@@ -7,12 +13,54 @@ use serde::Serialize;
 #[serde(transparent)]
 pub struct Acceleration(pub AccelerationData);
 
-impl Acceleration {
-    pub fn new(x: f64, y: f64, z: f64) -> Acceleration {
-        Acceleration(AccelerationData { x, y, z })
-    }
-}
+/// 3軸の角速度を返す
+#[derive(Serialize, PartialEq, Clone, Debug)]
+#[serde(transparent)]
+pub struct AngularRate(pub AngularRateData);
+
+/// 温度を返す
+#[derive(Serialize, PartialEq, Clone, Debug)]
+#[serde(transparent)]
+pub struct Temperature(pub TemperatureData);
 
 pub trait Accelerometer {
     fn fetch(&mut self) -> Result<Acceleration>;
 }
+
+pub trait Gyroscope {
+    fn fetch(&mut self) -> Result<AngularRate>;
+}
+
+pub trait Thermometer {
+    fn fetch(&mut self) -> Result<Temperature>;
+}
+
+impl<D> Accelerometer for Lsm6sdrx<D>
+where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+{
+    fn fetch(&mut self) -> Result<Acceleration> {
+        Ok(Acceleration(self.fetch_acceleration()?))
+    }
+}
+
+impl<D> Gyroscope for Lsm6sdrx<D>
+where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+{
+    fn fetch(&mut self) -> Result<AngularRate> {
+        Ok(AngularRate(self.fetch_angular_rate()?))
+    }
+}
+
+impl<D> Thermometer for Lsm6sdrx<D>
+where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+{
+    fn fetch(&mut self) -> Result<Temperature> {
+        Ok(Temperature(self.fetch_temperature()?))
+    }
+}
@@ -1,11 +1,13 @@
 mod imu;
-mod lsm6dsrx;
+mod sampler;
+mod storage;
 
 use std::sync::{Arc, Mutex};
 
 use anyhow::{Context as _, Result};
 use embedded_hal::spi::MODE_3;
 use esp_idf_hal::{
+    delay::Delay,
     gpio::Pins,
     interrupt::IntrFlags,
     modem::WifiModemPeripheral,
@@ -21,11 +23,18 @@ use esp_idf_svc::{
     wifi::{AuthMethod, BlockingWifi, EspWifi},
 };
 
-use crate::{imu::Accelerometer as _, lsm6dsrx::Lsm6sdrx};
+use lsm6dsrx::{Lsm6sdrx, Orientation, SixPositionCalibration, SpiInterface};
+use schema::Temperature;
+
+use crate::{sampler::SampleBuffer, storage::SettingsStorage};
 
 const STACK_SIZE: usize = 10240;
+/// サンプリングタスクのスタックサイズ
+const SAMPLER_STACK_SIZE: usize = 8192;
 const WIFI_SSID: Option<&str> = option_env!("WIFI_SSID");
 const WIFI_PASSWORD: Option<&str> = option_env!("WIFI_PASSWORD");
+/// 加速度計のオフセットのキャリブレーションで平均をとるサンプル数
+const ACCEL_CALIBRATION_SAMPLES: u32 = 100;
 
 fn main() -> Result<()> {
     // It is necessary to call this function once. Otherwise some patches to the runtime
@@ -39,6 +48,7 @@ fn main() -> Result<()> {
         spi2,
         pins:
             Pins {
+                gpio4: int1,   // INT1
                 gpio5: sdi,    // MISO/SDI
                 gpio6: sdo,    // MOSI/SDO
                 gpio7: sclk,   // sclk
@@ -51,6 +61,10 @@ fn main() -> Result<()> {
     let sys_loop = EspSystemEventLoop::take().context("Failed to take system event loop.")?;
     let nvs = EspDefaultNvsPartition::take().context("Failed to take nvs.")?;
 
+    let storage = SettingsStorage::new(nvs.clone()).context("Failed to open settings.")?;
+    let settings = storage.load();
+    log::info!("Settings: {settings:?}");
+
     let wifi = connect_wifi(
         WIFI_SSID.expect("`WIFI_SSID` not set."),
         WIFI_PASSWORD.expect("`WIFI_PASS` not set."),
@@ -78,18 +92,48 @@ fn main() -> Result<()> {
             .context("Failed to create SPI device.")?
     };
 
-    let imu = Arc::new(Mutex::new(Lsm6sdrx::new(spi_device)?));
+    let mut delay = Delay::new_default();
+    let mut imu = Lsm6sdrx::with_config(
+        SpiInterface::new(spi_device),
+        settings.sensor.clone(),
+        &mut delay,
+    )
+    .context("Failed to initialize LSM6DSRX.")?;
+    imu.set_accel_calibration(settings.accel_calibration.clone());
+    let (fifo, tap) = (settings.fifo.clone(), settings.tap.clone());
+    let storage = Arc::new(Mutex::new(storage));
+    let settings = Arc::new(Mutex::new(settings));
     log::info!("Lsm6sdrx initialized.");
 
+    // HTTP ハンドラは IMU を直接読まずに、サンプリングタスクが読んだ値を返すか、サンプリングタスクに頼む
+    let buffer = Arc::new(Mutex::new(SampleBuffer::new(imu.config())));
+    let (sampler, commands) = sampler::channel();
+    {
+        let buffer = Arc::clone(&buffer);
+        std::thread::Builder::new()
+            .name("sampler".to_string())
+            .stack_size(SAMPLER_STACK_SIZE)
+            .spawn(move || {
+                if let Err(error) = sampler::run(imu, int1, buffer, commands, fifo, tap) {
+                    log::error!("Sampler stopped: {error:?}");
+                }
+            })
+            .context("Failed to spawn sampler.")?;
+    }
+
     let mut server: EspHttpServer<'_> = create_server().context("Failed to create server.")?;
 
     {
-        let imu = Arc::clone(&imu);
+        let buffer = Arc::clone(&buffer);
         server.fn_handler("/accel", Method::Get, move |req| -> Result<()> {
             use esp_idf_hal::io::Write;
+            // 書き込みの間にサンプリングタスクを止めないように、すぐにロックを外す
+            let data = buffer
+                .lock()
+                .expect("Failed to lock mutex.")
+                .latest_acceleration()
+                .context("No acceleration sampled yet.")?;
             let mut res = req.into_ok_response()?;
-            let mut imu = imu.lock().expect("Failed to lock mutex.");
-            let data = imu.fetch()?;
             let json_text = serde_json::to_string_pretty(&data)?;
             writeln!(&mut res, "{json_text}")?;
             Ok(())
@@ -97,17 +141,202 @@ fn main() -> Result<()> {
     }
 
     {
+        let buffer = Arc::clone(&buffer);
         server.fn_handler("/gyro", Method::Get, move |req| -> Result<()> {
             use esp_idf_hal::io::Write;
+            let data = buffer
+                .lock()
+                .expect("Failed to lock mutex.")
+                .latest_angular_rate()
+                .context("No angular rate sampled yet.")?;
+            let mut res = req.into_ok_response()?;
+            let json_text = serde_json::to_string_pretty(&data)?;
+            writeln!(&mut res, "{json_text}")?;
+            Ok(())
+        })?;
+    }
+
+    {
+        let buffer = Arc::clone(&buffer);
+        server.fn_handler("/gyro/bias", Method::Get, move |req| -> Result<()> {
+            use esp_idf_hal::io::Write;
+            let data = buffer.lock().expect("Failed to lock mutex.").gyro_bias();
+            let mut res = req.into_ok_response()?;
+            let json_text = serde_json::to_string_pretty(&data)?;
+            writeln!(&mut res, "{json_text}")?;
+            Ok(())
+        })?;
+    }
+
+    {
+        let buffer = Arc::clone(&buffer);
+        server.fn_handler("/temperature", Method::Get, move |req| -> Result<()> {
+            use esp_idf_hal::io::Write;
+            let celsius = buffer
+                .lock()
+                .expect("Failed to lock mutex.")
+                .latest_temperature()
+                .context("No temperature sampled yet.")?;
+            let data = Temperature { celsius };
+            let mut res = req.into_ok_response()?;
+            let json_text = serde_json::to_string_pretty(&data)?;
+            writeln!(&mut res, "{json_text}")?;
+            Ok(())
+        })?;
+    }
+
+    {
+        let buffer = Arc::clone(&buffer);
+        server.fn_handler("/motion", Method::Get, move |req| -> Result<()> {
+            use esp_idf_hal::io::Write;
+            let data = buffer
+                .lock()
+                .expect("Failed to lock mutex.")
+                .latest_motion()
+                .context("No motion sampled yet.")?;
+            let mut res = req.into_ok_response()?;
+            let json_text = serde_json::to_string_pretty(&data)?;
+            writeln!(&mut res, "{json_text}")?;
+            Ok(())
+        })?;
+    }
+
+    {
+        let buffer = Arc::clone(&buffer);
+        server.fn_handler("/samples", Method::Get, move |req| -> Result<()> {
+            use esp_idf_hal::io::Write;
+            // 前回から溜まった分を返す
+            let data = buffer.lock().expect("Failed to lock mutex.").take();
             let mut res = req.into_ok_response()?;
-            // そのうちジャイロもやる
-            let data = None as Option<()>;
             let json_text = serde_json::to_string_pretty(&data)?;
             writeln!(&mut res, "{json_text}")?;
             Ok(())
         })?;
     }
 
+    {
+        let buffer = Arc::clone(&buffer);
+        server.fn_handler("/taps", Method::Get, move |req| -> Result<()> {
+            use esp_idf_hal::io::Write;
+            let data = buffer.lock().expect("Failed to lock mutex.").take_taps();
+            let mut res = req.into_ok_response()?;
+            let json_text = serde_json::to_string_pretty(&data)?;
+            writeln!(&mut res, "{json_text}")?;
+            Ok(())
+        })?;
+    }
+
+    {
+        let sampler = sampler.clone();
+        server.fn_handler(
+            "/diagnostics/self-test",
+            Method::Get,
+            move |req| -> Result<()> {
+                use esp_idf_hal::io::Write;
+                let report = sampler.self_test()?;
+                if !report.passed() {
+                    log::warn!("Self-test failed: {report:?}");
+                }
+                let mut res = req.into_ok_response()?;
+                let json_text = serde_json::to_string_pretty(&report)?;
+                writeln!(&mut res, "{json_text}")?;
+                Ok(())
+            },
+        )?;
+    }
+
+    {
+        let sampler = sampler.clone();
+        let storage = Arc::clone(&storage);
+        let settings = Arc::clone(&settings);
+        server.fn_handler(
+            "/calibration/accel-offset",
+            Method::Post,
+            move |req| -> Result<()> {
+                use esp_idf_hal::io::Write;
+                let (offset, config) = sampler.calibrate_accel_offset(ACCEL_CALIBRATION_SAMPLES)?;
+
+                // 次の起動でも同じオフセットを使う
+                let mut settings = settings.lock().expect("Failed to lock mutex.");
+                settings.sensor = config;
+                storage
+                    .lock()
+                    .expect("Failed to lock mutex.")
+                    .save(&settings)?;
+
+                let mut res = req.into_ok_response()?;
+                let json_text = serde_json::to_string_pretty(&offset)?;
+                writeln!(&mut res, "{json_text}")?;
+                Ok(())
+            },
+        )?;
+    }
+
+    {
+        let sampler = sampler.clone();
+        server.fn_handler("/debug/registers", Method::Get, move |req| -> Result<()> {
+            use esp_idf_hal::io::Write;
+            let registers = sampler.dump_registers()?;
+            let mut res = req.into_ok_response()?;
+            let json_text = serde_json::to_string_pretty(&registers)?;
+            writeln!(&mut res, "{json_text}")?;
+            Ok(())
+        })?;
+    }
+
+    let six_position = Arc::new(Mutex::new(SixPositionCalibration::new()));
+    for orientation in Orientation::ALL {
+        let sampler = sampler.clone();
+        let six_position = Arc::clone(&six_position);
+        let uri = format!("/calibration/accel/{}", orientation.as_str());
+        server.fn_handler(&uri, Method::Post, move |req| -> Result<()> {
+            use esp_idf_hal::io::Write;
+            let mean = sampler.average_acceleration(ACCEL_CALIBRATION_SAMPLES)?;
+
+            // 残りの姿勢を返す
+            let mut six_position = six_position.lock().expect("Failed to lock mutex.");
+            six_position.record(orientation, mean);
+            let missing = six_position.missing().collect::<Vec<_>>();
+            let mut res = req.into_ok_response()?;
+            let json_text = serde_json::to_string_pretty(&missing)?;
+            writeln!(&mut res, "{json_text}")?;
+            Ok(())
+        })?;
+    }
+
+    {
+        let storage = Arc::clone(&storage);
+        let settings = Arc::clone(&settings);
+        let six_position = Arc::clone(&six_position);
+        server.fn_handler(
+            "/calibration/accel/solve",
+            Method::Post,
+            move |req| -> Result<()> {
+                use esp_idf_hal::io::Write;
+                let mut six_position = six_position.lock().expect("Failed to lock mutex.");
+                let calibration = six_position
+                    .solve()
+                    .context("Failed to solve six-position calibration.")?;
+                sampler.set_accel_calibration(calibration.clone())?;
+
+                // 次の起動でも同じ補正を使う
+                let mut settings = settings.lock().expect("Failed to lock mutex.");
+                settings.accel_calibration = calibration.clone();
+                storage
+                    .lock()
+                    .expect("Failed to lock mutex.")
+                    .save(&settings)?;
+                // 適用と保存に失敗したら、測り直さずにやり直せるように残しておく
+                *six_position = SixPositionCalibration::new();
+
+                let mut res = req.into_ok_response()?;
+                let json_text = serde_json::to_string_pretty(&calibration)?;
+                writeln!(&mut res, "{json_text}")?;
+                Ok(())
+            },
+        )?;
+    }
+
     // Keep server running beyond when main() returns (forever)
     // Do not call this if you ever want to stop or access it later.
     // Otherwise you can either add an infinite loop so the main task
@@ -0,0 +1,460 @@
+//! INT1 の割り込みで IMU を読み、HTTP ハンドラが読むバッファに入れるタスク
+//!
+//! センサを読むタイミングがネットワークの遅延に左右されないように、IMU を読むのはこのタスクだけにする。
+//! キャリブレーションなどの IMU を使う処理も、[`SamplerHandle`] からこのタスクに頼む
+
+use std::{
+    collections::VecDeque,
+    fmt::Debug,
+    num::NonZeroU32,
+    sync::{mpsc, Arc, Mutex},
+};
+
+use anyhow::{anyhow, Context as _, Result};
+use esp_idf_hal::{
+    delay::{Delay, TickType},
+    gpio::{InputPin, InterruptType, PinDriver, Pull},
+    peripheral::Peripheral,
+    task::notification::Notification,
+};
+use lsm6dsrx::{
+    AccelUserOffset, FifoConfig, FifoMode, FifoSample, GyroBias, InterruptConfig, InterruptRoute,
+    InterruptSignal, Lsm6dsrxConfig, Lsm6dsrxError, Lsm6sdrx, RegisterBus, RegisterSnapshot,
+    SelfTestReport, StatusReg, TapConfig,
+};
+use schema::{Acceleration, AngularRate, Calibration, MotionSample, TapEvent};
+use serde::Serialize;
+
+use crate::imu::{Accelerometer, Gyroscope, Thermometer};
+
+/// 取り出されずに残しておくサンプルの時間 [ms]
+///
+/// 加速度、角速度それぞれ出力レートでこの時間分を残し、溢れた分は数だけ [`Samples`] で返す
+const SAMPLE_RETENTION_MS: u32 = 500;
+
+/// 取り出されずに残しておくタップの数
+const TAP_BUFFER_LEN: usize = 32;
+
+/// 割り込みがなくても、頼まれた処理を見に行く間隔 [ms]
+const COMMAND_POLL_INTERVAL_MS: u64 = 100;
+
+/// INT1 で何を待つか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    /// 加速度、角速度の data-ready ごとに読む
+    DataReady,
+    /// FIFO が watermark に達したらまとめて読む
+    FifoWatermark,
+}
+
+impl Trigger {
+    /// FIFO を使う設定なら watermark、使わない設定なら data-ready で読む
+    pub fn from_fifo_config(fifo: &FifoConfig) -> Trigger {
+        if fifo.mode == FifoMode::Bypass {
+            Trigger::DataReady
+        } else {
+            Trigger::FifoWatermark
+        }
+    }
+
+    /// INT1 にだけイベントを出す設定
+    ///
+    /// タップを検出するなら INT1 に出して、`TAP_SRC` を読むまで保持させる
+    fn interrupt_config(self, tap: &TapConfig) -> InterruptConfig {
+        let route = match self {
+            Trigger::DataReady => InterruptRoute::new()
+                .accel_data_ready(true)
+                .gyro_data_ready(true),
+            Trigger::FifoWatermark => InterruptRoute::new().fifo_watermark(true),
+        };
+        if !tap.is_enabled() {
+            return InterruptConfig::new().int1(route);
+        }
+        InterruptConfig::new()
+            .int1(route.single_tap(true).double_tap(true))
+            .event_signal(InterruptSignal::Latched)
+    }
+}
+
+/// FIFO、タップの検出、INT1 を設定する
+///
+/// リセットすると設定が消えるので、セルフテストのあとにも呼ぶ
+fn configure<D>(imu: &mut Lsm6sdrx<D>, fifo: &FifoConfig, tap: &TapConfig) -> Result<Trigger>
+where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+{
+    let trigger = Trigger::from_fifo_config(fifo);
+    imu.configure_fifo(fifo)
+        .context("Failed to configure FIFO.")?;
+    imu.configure_tap(tap)
+        .context("Failed to configure tap detection.")?;
+    imu.configure_interrupts(&trigger.interrupt_config(tap))
+        .context("Failed to configure interrupts.")?;
+    Ok(trigger)
+}
+
+/// HTTP ハンドラに渡すサンプル
+#[derive(Debug)]
+pub struct SampleBuffer {
+    acceleration: VecDeque<Acceleration>,
+    angular_rate: VecDeque<AngularRate>,
+    acceleration_len: usize,
+    angular_rate_len: usize,
+    dropped_acceleration: usize,
+    dropped_angular_rate: usize,
+    latest_acceleration: Option<Acceleration>,
+    latest_angular_rate: Option<AngularRate>,
+    /// [℃]
+    latest_temperature: Option<f64>,
+    taps: VecDeque<TapEvent>,
+    gyro_bias: GyroBias,
+}
+
+/// [`SampleBuffer::take`] で取り出したサンプル
+#[derive(Debug, Serialize)]
+pub struct Samples {
+    pub acceleration: Vec<Acceleration>,
+    pub angular_rate: Vec<AngularRate>,
+    /// 取り出される前にバッファが溢れて捨てた加速度の数
+    pub dropped_acceleration: usize,
+    /// 取り出される前にバッファが溢れて捨てた角速度の数
+    pub dropped_angular_rate: usize,
+}
+
+/// 出力レート `frequency` [Hz] で [`SAMPLE_RETENTION_MS`] 分のサンプル数 (power-down でも 1 以上)
+fn retention_len(frequency: Option<f64>) -> usize {
+    frequency.map_or(1, |frequency| {
+        ((frequency * SAMPLE_RETENTION_MS as f64 / 1000.0).ceil() as usize).max(1)
+    })
+}
+
+impl SampleBuffer {
+    /// `config` の出力レートに合わせた大きさのバッファを作る
+    pub fn new(config: &Lsm6dsrxConfig) -> Self {
+        Self {
+            acceleration: VecDeque::new(),
+            angular_rate: VecDeque::new(),
+            acceleration_len: retention_len(config.accel_odr.frequency()),
+            angular_rate_len: retention_len(config.gyro_odr.frequency()),
+            dropped_acceleration: 0,
+            dropped_angular_rate: 0,
+            latest_acceleration: None,
+            latest_angular_rate: None,
+            latest_temperature: None,
+            taps: VecDeque::new(),
+            gyro_bias: GyroBias::default(),
+        }
+    }
+
+    pub fn latest_acceleration(&self) -> Option<Acceleration> {
+        self.latest_acceleration.clone()
+    }
+
+    pub fn latest_angular_rate(&self) -> Option<AngularRate> {
+        self.latest_angular_rate.clone()
+    }
+
+    /// [℃]
+    pub fn latest_temperature(&self) -> Option<f64> {
+        self.latest_temperature
+    }
+
+    /// 最新の加速度、角速度、温度をまとめて返す
+    pub fn latest_motion(&self) -> Option<MotionSample> {
+        Some(MotionSample {
+            acceleration: self.latest_acceleration()?,
+            angular_rate: self.latest_angular_rate()?,
+            temperature: self.latest_temperature?,
+        })
+    }
+
+    /// 最後に読んだときに推定されていた角速度計のゼロレートバイアス
+    pub fn gyro_bias(&self) -> GyroBias {
+        self.gyro_bias
+    }
+
+    /// 溜まっているサンプルをすべて取り出す
+    pub fn take(&mut self) -> Samples {
+        Samples {
+            acceleration: self.acceleration.drain(..).collect(),
+            angular_rate: self.angular_rate.drain(..).collect(),
+            dropped_acceleration: std::mem::take(&mut self.dropped_acceleration),
+            dropped_angular_rate: std::mem::take(&mut self.dropped_angular_rate),
+        }
+    }
+
+    /// 溜まっているタップをすべて取り出す
+    pub fn take_taps(&mut self) -> Vec<TapEvent> {
+        self.taps.drain(..).collect()
+    }
+
+    fn push_acceleration(&mut self, acceleration: Acceleration) {
+        // 古いものから捨てる
+        if self.acceleration.len() == self.acceleration_len {
+            self.acceleration.pop_front();
+            self.dropped_acceleration += 1;
+        }
+        self.acceleration.push_back(acceleration.clone());
+        self.latest_acceleration = Some(acceleration);
+    }
+
+    fn push_angular_rate(&mut self, angular_rate: AngularRate) {
+        if self.angular_rate.len() == self.angular_rate_len {
+            self.angular_rate.pop_front();
+            self.dropped_angular_rate += 1;
+        }
+        self.angular_rate.push_back(angular_rate.clone());
+        self.latest_angular_rate = Some(angular_rate);
+    }
+
+    fn push_tap(&mut self, tap: TapEvent) {
+        if self.taps.len() == TAP_BUFFER_LEN {
+            self.taps.pop_front();
+        }
+        self.taps.push_back(tap);
+    }
+
+    fn push_sample(&mut self, sample: FifoSample) {
+        match sample {
+            FifoSample::Acceleration(acceleration) => self.push_acceleration(acceleration),
+            FifoSample::AngularRate(angular_rate) => self.push_angular_rate(angular_rate),
+            FifoSample::Temperature(temperature) => self.latest_temperature = Some(temperature),
+            FifoSample::Timestamp(_) => {}
+        }
+    }
+}
+
+/// 返事を返すチャンネル
+type Reply<T> = mpsc::Sender<Result<T>>;
+
+/// HTTP ハンドラからサンプリングタスクに頼む、IMU を使う処理
+enum Command {
+    SelfTest(Reply<SelfTestReport>),
+    CalibrateAccelOffset(u32, Reply<(AccelUserOffset, Lsm6dsrxConfig)>),
+    AverageAcceleration(u32, Reply<[f64; 3]>),
+    SetAccelCalibration(Calibration, Reply<()>),
+    DumpRegisters(Reply<Vec<RegisterSnapshot>>),
+}
+
+/// [`SamplerHandle`] から頼まれた処理を受け取る側
+pub struct Commands(mpsc::Receiver<Command>);
+
+/// サンプリングタスクに IMU を使う処理を頼み、終わるまで待つ
+#[derive(Clone)]
+pub struct SamplerHandle(mpsc::Sender<Command>);
+
+/// [`run`] に渡す [`Commands`] と、それに処理を頼む [`SamplerHandle`] を作る
+pub fn channel() -> (SamplerHandle, Commands) {
+    let (sender, receiver) = mpsc::channel();
+    (SamplerHandle(sender), Commands(receiver))
+}
+
+impl SamplerHandle {
+    /// セルフテストを行う (終わったあとに FIFO などの設定は戻される)
+    pub fn self_test(&self) -> Result<SelfTestReport> {
+        self.request(Command::SelfTest)
+    }
+
+    /// 加速度計のオフセットを `samples` 個の平均から求めて書き込み、そのオフセットを含む設定も返す
+    pub fn calibrate_accel_offset(
+        &self,
+        samples: u32,
+    ) -> Result<(AccelUserOffset, Lsm6dsrxConfig)> {
+        self.request(|reply| Command::CalibrateAccelOffset(samples, reply))
+    }
+
+    /// 加速度の `samples` 個の平均 [mg]
+    pub fn average_acceleration(&self, samples: u32) -> Result<[f64; 3]> {
+        self.request(|reply| Command::AverageAcceleration(samples, reply))
+    }
+
+    pub fn set_accel_calibration(&self, calibration: Calibration) -> Result<()> {
+        self.request(|reply| Command::SetAccelCalibration(calibration, reply))
+    }
+
+    pub fn dump_registers(&self) -> Result<Vec<RegisterSnapshot>> {
+        self.request(Command::DumpRegisters)
+    }
+
+    fn request<T>(&self, command: impl FnOnce(Reply<T>) -> Command) -> Result<T> {
+        let (reply, result) = mpsc::channel();
+        self.0
+            .send(command(reply))
+            .map_err(|_| anyhow!("Sampler stopped."))?;
+        result.recv().context("Sampler stopped.")?
+    }
+}
+
+/// FIFO、タップの検出、INT1 を設定してから、INT1 が High になるのを待って IMU を読み、
+/// `buffer` に入れ続ける
+///
+/// 設定に失敗したときだけ戻る
+pub fn run<D, P>(
+    mut imu: Lsm6sdrx<D>,
+    int1: impl Peripheral<P = P> + 'static,
+    buffer: Arc<Mutex<SampleBuffer>>,
+    commands: Commands,
+    fifo: FifoConfig,
+    tap: TapConfig,
+) -> Result<()>
+where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+    P: InputPin,
+{
+    let trigger = configure(&mut imu, &fifo, &tap)?;
+
+    // 割り込みを購読している間は drop されないように、ピンより先に作る
+    let notification = Notification::new();
+    let notifier = notification.notifier();
+
+    let mut int1 = PinDriver::input(int1).context("Failed to create INT1 pin driver.")?;
+    int1.set_pull(Pull::Down)?;
+    // latched の割り込みは読むまで High のままなので、エッジではなくレベルで待って取りこぼさない
+    int1.set_interrupt_type(InterruptType::HighLevel)?;
+    // Safety: `notification` は `int1` より後に drop される
+    unsafe {
+        int1.subscribe(move || {
+            notifier.notify_and_yield(NonZeroU32::new(1).unwrap());
+        })?;
+    }
+    log::info!("Sampler started: {trigger:?}");
+
+    let poll_interval = TickType::new_millis(COMMAND_POLL_INTERVAL_MS).ticks();
+    let mut samples = Vec::new();
+    loop {
+        // 割り込みは発生するたびに無効になる
+        int1.enable_interrupt()?;
+        if notification.wait(poll_interval).is_some() {
+            sample(&mut imu, trigger, &tap, &mut samples, &buffer);
+        }
+
+        while let Ok(command) = commands.0.try_recv() {
+            handle(&mut imu, command, &fifo, &tap);
+        }
+    }
+}
+
+/// 出力されている値を読んで `buffer` に入れる
+///
+/// SPI で読んでいる間は HTTP ハンドラを待たせないように、読み終えてから `buffer` をロックする
+fn sample<D>(
+    imu: &mut Lsm6sdrx<D>,
+    trigger: Trigger,
+    tap: &TapConfig,
+    samples: &mut Vec<FifoSample>,
+    buffer: &Mutex<SampleBuffer>,
+) where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+{
+    match trigger {
+        Trigger::DataReady => {
+            if let Err(error) = fetch_ready(imu, samples) {
+                log::warn!("Failed to fetch samples: {error:?}");
+            }
+        }
+        Trigger::FifoWatermark => match imu.drain_fifo_with(|sample| samples.push(sample)) {
+            Ok(_) => {}
+            // 溢れる前のデータは読めている
+            Err(Lsm6dsrxError::FifoOverrun) => log::warn!("FIFO overrun."),
+            Err(error) => log::warn!("Failed to drain FIFO: {error:?}"),
+        },
+    }
+
+    // タップの割り込みは latched なので、`TAP_SRC` を読むまで INT1 が戻らない
+    let tap = match tap.is_enabled().then(|| imu.tap_event()) {
+        Some(Ok(tap)) => tap,
+        Some(Err(error)) => {
+            log::warn!("Failed to read tap source: {error:?}");
+            None
+        }
+        None => None,
+    };
+
+    let mut buffer = buffer.lock().expect("Failed to lock mutex.");
+    for sample in samples.drain(..) {
+        buffer.push_sample(sample);
+    }
+    if let Some(tap) = tap {
+        log::info!("Tap detected: {tap:?}");
+        buffer.push_tap(tap);
+    }
+    buffer.gyro_bias = imu.gyro_bias();
+}
+
+/// data-ready で起きたときに、出力されている値だけを待たずに読む
+///
+/// ODR が違ったり片方が止まっていたりすると、加速度と角速度の片方だけが出力されている
+fn fetch_ready<D>(imu: &mut Lsm6sdrx<D>, samples: &mut Vec<FifoSample>) -> Result<()>
+where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+{
+    let status = imu.status()?;
+    if status.contains(StatusReg::XLDA | StatusReg::GDA) {
+        // 同じ出力周期の値として読み、バイアスの推定も更新する
+        let MotionSample {
+            acceleration,
+            angular_rate,
+            temperature,
+        } = imu.fetch_motion()?;
+        samples.extend([
+            FifoSample::Acceleration(acceleration),
+            FifoSample::AngularRate(angular_rate),
+            FifoSample::Temperature(temperature),
+        ]);
+        return Ok(());
+    }
+    if status.contains(StatusReg::XLDA) {
+        samples.push(FifoSample::Acceleration(Accelerometer::fetch(imu)?.0));
+    }
+    if status.contains(StatusReg::GDA) {
+        samples.push(FifoSample::AngularRate(Gyroscope::fetch(imu)?.0));
+    }
+    if status.contains(StatusReg::TDA) {
+        samples.push(FifoSample::Temperature(Thermometer::fetch(imu)?.0.celsius));
+    }
+    Ok(())
+}
+
+/// 頼まれた処理をして返事を返す
+fn handle<D>(imu: &mut Lsm6sdrx<D>, command: Command, fifo: &FifoConfig, tap: &TapConfig)
+where
+    D: RegisterBus,
+    D::Error: Debug + Sync + Send + 'static,
+{
+    let mut delay = Delay::new_default();
+    // 頼んだハンドラがもう待っていなければ、返事は捨てる
+    match command {
+        Command::SelfTest(reply) => {
+            let report = imu
+                .self_test(&mut delay)
+                .context("Failed to run self-test.");
+            // セルフテストは成否にかかわらずリセットして終わるので、先に設定を戻す
+            let configured = configure(imu, fifo, tap);
+            let _ = reply.send(report.and_then(|report| configured.map(|_| report)));
+        }
+        Command::CalibrateAccelOffset(samples, reply) => {
+            let offset = imu
+                .calibrate_accel_offset(samples, &mut delay)
+                .context("Failed to calibrate accelerometer offset.");
+            let _ = reply.send(offset.map(|offset| (offset, imu.config().clone())));
+        }
+        Command::AverageAcceleration(samples, reply) => {
+            let mean = imu
+                .average_acceleration(samples, &mut delay)
+                .context("Failed to measure acceleration.");
+            let _ = reply.send(mean);
+        }
+        Command::SetAccelCalibration(calibration, reply) => {
+            imu.set_accel_calibration(calibration);
+            let _ = reply.send(Ok(()));
+        }
+        Command::DumpRegisters(reply) => {
+            let registers = imu.dump_registers().context("Failed to dump registers.");
+            let _ = reply.send(registers);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use anyhow::{bail, Context as _, Result};
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault};
+use settings::{Settings, SETTINGS_BLOB_MAX};
+
+/// NVS の名前空間
+const NAMESPACE: &str = "observer";
+
+/// 設定を保存するキー
+const SETTINGS_KEY: &str = "settings";
+
+/// 設定を NVS に読み書きする
+pub struct SettingsStorage {
+    nvs: EspNvs<NvsDefault>,
+}
+
+impl SettingsStorage {
+    pub fn new(partition: EspDefaultNvsPartition) -> Result<SettingsStorage> {
+        let nvs =
+            EspNvs::new(partition, NAMESPACE, true).context("Failed to open NVS namespace.")?;
+        Ok(SettingsStorage { nvs })
+    }
+
+    /// 保存されている設定を読む。ない、または読めない場合はデフォルト値を返す
+    pub fn load(&self) -> Settings {
+        match self.read_blob() {
+            Ok(blob) => Settings::decode_or_default(blob.as_deref()),
+            Err(error) => {
+                log::warn!("Failed to read settings: {error:?}, using defaults.");
+                Settings::default()
+            }
+        }
+    }
+
+    /// 保存されている blob を、保存されている大きさのまま読む
+    fn read_blob(&self) -> Result<Option<Vec<u8>>> {
+        let Some(len) = self
+            .nvs
+            .blob_len(SETTINGS_KEY)
+            .context("Failed to get settings size.")?
+        else {
+            return Ok(None);
+        };
+        let mut buf = vec![0u8; len];
+        let read = self
+            .nvs
+            .get_blob(SETTINGS_KEY, &mut buf)
+            .context("Failed to read settings.")?
+            .map(|blob| blob.len());
+        Ok(read.map(|read| {
+            buf.truncate(read);
+            buf
+        }))
+    }
+
+    /// 設定を保存する
+    pub fn save(&mut self, settings: &Settings) -> Result<()> {
+        let blob = settings.encode().context("Failed to encode settings.")?;
+        if blob.len() > SETTINGS_BLOB_MAX {
+            bail!(
+                "Settings too large: {} bytes (max {SETTINGS_BLOB_MAX}).",
+                blob.len()
+            );
+        }
+        self.nvs
+            .set_blob(SETTINGS_KEY, &blob)
+            .context("Failed to write settings.")?;
+        Ok(())
+    }
+}